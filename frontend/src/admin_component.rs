@@ -0,0 +1,321 @@
+use gloo_storage::{SessionStorage, Storage};
+use web_sys::HtmlInputElement;
+use yew::platform::spawn_local;
+use yew::{function_component, html, use_effect_with, use_node_ref, use_state, Callback, Html};
+use shared::messages::{AdminAssets, AdminGameDetail, AdminGameSummary, AdminTemplate, ErrorCode, ErrorResponse, Verdict};
+use crate::locale::{t, tf};
+use crate::server_query::{admin_delete_game, admin_end_game, admin_fetch, admin_reload_assets};
+use crate::to_html::{ToHtmlEx, ToHtmlExArgs};
+
+const ADMIN_TOKEN_KEY: &str = "admin_token";
+
+#[derive(Clone, PartialEq, Default)]
+struct Dashboard {
+    games: Vec<AdminGameSummary>,
+    templates: Vec<AdminTemplate>,
+    assets: Option<AdminAssets>,
+}
+
+fn format_age(created: i64) -> String {
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+    match (now - created).max(0) {
+        s if s < 60 => format!("{s}s"),
+        s if s < 3600 => format!("{}m", s / 60),
+        s => format!("{}h {}m", s / 3600, s % 3600 / 60),
+    }
+}
+
+fn is_unauthorized(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<ErrorResponse>()
+        .is_some_and(|e| e.code == ErrorCode::Unauthorized)
+}
+
+fn error_message(err: &anyhow::Error) -> String {
+    match err.downcast_ref::<ErrorResponse>() {
+        Some(e) => e.message.clone(),
+        None => err.to_string(),
+    }
+}
+
+fn game_state_label(game: &AdminGameSummary) -> String {
+    if game.game_ended {
+        t("admin.state_ended")
+    } else if game.pending {
+        t("admin.state_pending")
+    } else {
+        t("admin.state_active")
+    }
+}
+
+fn render_detail(detail: &AdminGameDetail) -> Html {
+    let args = ToHtmlExArgs { state: &detail.state };
+    html! {
+        <div class="admin-detail">
+            <h2>{ detail.summary.token.clone() }</h2>
+            <p>{ tf("admin.identity", &[&detail.identity]) }</p>
+            if let Some(template) = &detail.template {
+                <p>{ tf("admin.template", &[template]) }</p>
+            }
+            if let Some(info) = &detail.state.custom_info {
+                <p>{ tf("admin.comment", &[info.comment.as_deref().unwrap_or("")]) }</p>
+            }
+            <ol class="admin-records">
+                { for detail.state.records.iter().map(|record| {
+                    let verdict = record.answers.as_ref()
+                        .and_then(|a| a.verdict.clone())
+                        .unwrap_or(Verdict::NotSet);
+                    let comment = record.answers.as_ref()
+                        .and_then(|a| a.comment.clone())
+                        .unwrap_or_default();
+                    html! {
+                        <li>
+                            { record.questions.to_html(&args) }
+                            { verdict.to_html(&args) }
+                            <div class="comment">{ comment }</div>
+                        </li>
+                    }
+                })}
+            </ol>
+            if let Some(pending) = &detail.state.pending_question {
+                <p>{ tf("admin.pending_question", &[&pending.text]) }</p>
+            }
+        </div>
+    }
+}
+
+/// Operator dashboard of the live games; the admin token is kept in the
+/// session storage only.
+#[function_component(Admin)]
+pub fn admin() -> Html {
+    let admin_token = use_state(|| SessionStorage::get::<String>(ADMIN_TOKEN_KEY).ok());
+    let dashboard = use_state(Dashboard::default);
+    let detail = use_state(|| None::<AdminGameDetail>);
+    let error = use_state(|| None::<String>);
+    let version = use_state(|| 0u32);
+    let token_ref = use_node_ref();
+
+    let sign_out = {
+        let (admin_token, dashboard, detail) = (admin_token.clone(), dashboard.clone(), detail.clone());
+        move || {
+            SessionStorage::delete(ADMIN_TOKEN_KEY);
+            admin_token.set(None);
+            dashboard.set(Dashboard::default());
+            detail.set(None);
+        }
+    };
+
+    let report_error = {
+        let (error, sign_out) = (error.clone(), sign_out.clone());
+        move |err: anyhow::Error| {
+            if is_unauthorized(&err) {
+                sign_out();
+            }
+            error.set(Some(error_message(&err)));
+        }
+    };
+
+    use_effect_with(((*admin_token).clone(), *version), {
+        let (dashboard, error, report_error) = (dashboard.clone(), error.clone(), report_error.clone());
+        move |(token, _): &(Option<String>, u32)| {
+            if let Some(token) = token.clone() {
+                spawn_local(async move {
+                    let games = admin_fetch::<Vec<AdminGameSummary>>("games", &token).await;
+                    let templates = admin_fetch::<Vec<AdminTemplate>>("templates", &token).await;
+                    let assets = admin_fetch::<AdminAssets>("assets", &token).await;
+                    match (games, templates, assets) {
+                        (Ok(games), Ok(templates), Ok(assets)) => {
+                            dashboard.set(Dashboard { games, templates, assets: Some(assets) });
+                            error.set(None);
+                        }
+                        (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => report_error(err),
+                    }
+                });
+            }
+            || ()
+        }
+    });
+
+    let on_sign_in = {
+        let (admin_token, token_ref) = (admin_token.clone(), token_ref.clone());
+        Callback::from(move |_| {
+            let Some(input) = token_ref.cast::<HtmlInputElement>() else {
+                return;
+            };
+            let value = input.value().trim().to_string();
+            if value.is_empty() {
+                return;
+            }
+            let _ = SessionStorage::set(ADMIN_TOKEN_KEY, &value);
+            admin_token.set(Some(value));
+        })
+    };
+
+    let on_sign_out = {
+        let sign_out = sign_out.clone();
+        Callback::from(move |_| sign_out())
+    };
+
+    let on_refresh = {
+        let version = version.clone();
+        Callback::from(move |_| version.set(*version + 1))
+    };
+
+    let on_reload_assets = {
+        let (admin_token, version, report_error) = (admin_token.clone(), version.clone(), report_error.clone());
+        Callback::from(move |_| {
+            let Some(token) = (*admin_token).clone() else {
+                return;
+            };
+            let (version, report_error) = (version.clone(), report_error.clone());
+            spawn_local(async move {
+                if let Err(err) = admin_reload_assets(&token).await {
+                    report_error(err);
+                }
+                version.set(*version + 1);
+            });
+        })
+    };
+
+    let on_inspect = {
+        let (admin_token, detail, report_error) = (admin_token.clone(), detail.clone(), report_error.clone());
+        Callback::from(move |game: String| {
+            let Some(token) = (*admin_token).clone() else {
+                return;
+            };
+            let (detail, report_error) = (detail.clone(), report_error.clone());
+            spawn_local(async move {
+                match admin_fetch::<AdminGameDetail>(&format!("games/{game}"), &token).await {
+                    Ok(game) => detail.set(Some(game)),
+                    Err(err) => report_error(err),
+                }
+            });
+        })
+    };
+
+    let on_end = {
+        let (admin_token, detail, version, report_error) =
+            (admin_token.clone(), detail.clone(), version.clone(), report_error.clone());
+        Callback::from(move |game: String| {
+            let Some(token) = (*admin_token).clone() else {
+                return;
+            };
+            let (detail, version, report_error) = (detail.clone(), version.clone(), report_error.clone());
+            spawn_local(async move {
+                if let Err(err) = admin_end_game(&game, &token).await {
+                    report_error(err);
+                }
+                detail.set(None);
+                version.set(*version + 1);
+            });
+        })
+    };
+
+    let on_delete = {
+        let (admin_token, detail, version, report_error) =
+            (admin_token.clone(), detail.clone(), version.clone(), report_error.clone());
+        Callback::from(move |game: String| {
+            let Some(token) = (*admin_token).clone() else {
+                return;
+            };
+            let (detail, version, report_error) = (detail.clone(), version.clone(), report_error.clone());
+            spawn_local(async move {
+                if let Err(err) = admin_delete_game(&game, &token).await {
+                    report_error(err);
+                }
+                detail.set(None);
+                version.set(*version + 1);
+            });
+        })
+    };
+
+    let error_html = match &*error {
+        Some(message) => html! { <p class="admin-error">{ message.clone() }</p> },
+        None => html! {},
+    };
+
+    if admin_token.is_none() {
+        return html! {
+            <div class="admin">
+                <h1>{t("admin.title")}</h1>
+                { error_html }
+                <div class="input-group">
+                    <label for="admin-token">{t("admin.token_label")}</label>
+                    <input id="admin-token" type="password" ref={token_ref} />
+                </div>
+                <button onclick={on_sign_in}>{t("admin.sign_in")}</button>
+            </div>
+        };
+    }
+
+    html! {
+        <div class="admin">
+            <h1>{t("admin.title")}</h1>
+            <div class="admin-bar">
+                <button onclick={on_refresh}>{t("admin.refresh")}</button>
+                <button onclick={on_reload_assets}>{t("admin.reload_assets")}</button>
+                <button onclick={on_sign_out}>{t("admin.sign_out")}</button>
+            </div>
+            { error_html }
+            if let Some(assets) = &dashboard.assets {
+                <p>{ tf("admin.assets", &[&assets.version]) }</p>
+            }
+
+            <h2>{tf("admin.games", &[&dashboard.games.len().to_string()])}</h2>
+            <table class="admin-table">
+                <tr>
+                    <th>{t("admin.col_token")}</th>
+                    <th>{t("admin.col_lang")}</th>
+                    <th>{t("admin.col_age")}</th>
+                    <th>{t("admin.col_questions")}</th>
+                    <th>{t("admin.col_state")}</th>
+                    <th>{t("admin.col_assets")}</th>
+                    <th>{t("admin.col_variant")}</th>
+                    <th></th>
+                </tr>
+                { for dashboard.games.iter().map(|game| {
+                    let token = game.token.clone();
+                    let (inspect, end, delete) = (on_inspect.clone(), on_end.clone(), on_delete.clone());
+                    let (t1, t2, t3) = (token.clone(), token.clone(), token.clone());
+                    html! {
+                        <tr>
+                            <td>{ token }</td>
+                            <td>{ game.lang.to_code() }</td>
+                            <td>{ format_age(game.created) }</td>
+                            <td>{ game.questions }</td>
+                            <td>{ game_state_label(game) }</td>
+                            <td>{ game.asset_version.clone() }</td>
+                            <td>{ game.variant.clone().unwrap_or_default() }</td>
+                            <td>
+                                <button onclick={move |_| inspect.emit(t1.clone())}>{t("admin.inspect")}</button>
+                                if !game.game_ended {
+                                    <button onclick={move |_| end.emit(t2.clone())}>{t("admin.end")}</button>
+                                }
+                                <button onclick={move |_| delete.emit(t3.clone())}>{t("admin.delete")}</button>
+                            </td>
+                        </tr>
+                    }
+                })}
+            </table>
+
+            if let Some(detail) = &*detail {
+                { render_detail(detail) }
+            }
+
+            <h2>{tf("admin.templates", &[&dashboard.templates.len().to_string()])}</h2>
+            <table class="admin-table">
+                <tr>
+                    <th>{t("admin.col_token")}</th>
+                    <th>{t("admin.col_lang")}</th>
+                    <th>{t("admin.col_identity")}</th>
+                </tr>
+                { for dashboard.templates.iter().map(|template| html! {
+                    <tr>
+                        <td>{ template.token.clone() }</td>
+                        <td>{ template.template.language.to_code() }</td>
+                        <td>{ template.template.identity.clone() }</td>
+                    </tr>
+                })}
+            </table>
+        </div>
+    }
+}
@@ -1,5 +1,6 @@
 use gloo_storage::{LocalStorage, Storage};
 use yew::{function_component, html, use_state, Callback, Html};
+use crate::difficulty_selector_component::DifficultySelector;
 use crate::language_selector_component::LanguageSelector;
 use crate::locale::{get_current_language, t};
 use log::info;
@@ -42,6 +43,11 @@ pub fn app_home() -> Html {
     let on_new_game = create_game_and_navigate(Route::Game);
     let on_new_custom_game = create_game_and_navigate(Route::CustomGameDesign);
 
+    let on_history = {
+        let navigator = navigator.clone();
+        Callback::from(move |_| navigator.push(&Route::History))
+    };
+
     let on_language_changed = {
         let render_trigger = render_trigger.clone();
         Callback::from(move |_| {
@@ -54,6 +60,8 @@ pub fn app_home() -> Html {
             <h1>{ t("ui.page_title") }</h1>
             <button class="new-game" onclick={on_new_game}>{ t("ui.new_game") }</button>
             <button class="new-game" onclick={on_new_custom_game}>{ t("ui.new_custom_game") }</button>
+            <button class="new-game" onclick={on_history}>{ t("ui.history") }</button>
+            <DifficultySelector />
             <div class="language-bar">
                 <LanguageSelector on_language_changed={Some(on_language_changed)} />
             </div>
@@ -3,9 +3,13 @@ use std::rc::Rc;
 
 use log::info;
 use yew::{function_component, html, Callback, Html, Properties, Reducible, UseReducerHandle};
+use yew_router::components::Link;
 
+use crate::difficulty_selector_component::DifficultySelector;
 use crate::locale::t;
+use crate::server_query::transcript_path;
 use crate::to_html::{ToHtmlEx, ToHtmlExArgs};
+use crate::Route;
 use shared::messages::GameState;
 #[derive(Clone, PartialEq, Default)]
 pub struct BoardState {
@@ -50,6 +54,8 @@ pub struct BoardProps {
     pub board: UseReducerHandle<BoardState>,
     pub on_new_game: Callback<()>,
     pub on_custom_game: Callback<()>,
+    #[prop_or_default]
+    pub token: Option<String>,
 }
 
 #[function_component(Board)]
@@ -69,14 +75,16 @@ pub fn board(props: &BoardProps) -> Html {
 
 
     let mut game_ended = false;
+    let mut can_export = false;
 
 
     let game_board_html = if let Some(game_board) = &props.board.game {
         if game_board.game_ended {
             game_ended = true;
+            can_export = props.token.is_some();
         }
         let args = ToHtmlExArgs{
-            state: &game_board
+            state: game_board
         };
         game_board.to_html(&args)
     } else {
@@ -84,6 +92,19 @@ pub fn board(props: &BoardProps) -> Html {
         html! {}
     };
 
+    let export_html = match &props.token {
+        Some(token) if can_export => html! {
+            <div class="export-row">
+                { t("ui.export") }
+                <a href={transcript_path(token, "json")} download="">{ "JSON" }</a>
+                <a href={transcript_path(token, "md")} download="">{ "Markdown" }</a>
+                <a href={transcript_path(token, "html")} download="">{ "HTML" }</a>
+                <Link<Route> to={Route::Replay}>{ t("ui.replay") }</Link<Route>>
+            </div>
+        },
+        _ => html! {},
+    };
+
     html! {
         <div class="board">
 
@@ -91,11 +112,17 @@ pub fn board(props: &BoardProps) -> Html {
 
             { if game_ended {
                 html! {
+                  <>
                   <div class="button-row">
                     <button class="new-game" {onclick}>{ t("ui.new_game") }</button>
 
                     <button class="new-game" onclick={onclick_custom}>{ t("ui.new_custom_game") }</button>
+
+                    <Link<Route> to={Route::History}>{ t("ui.history") }</Link<Route>>
                   </div>
+                  <DifficultySelector />
+                  { export_html }
+                  </>
                 }
             } else {
                 html! {
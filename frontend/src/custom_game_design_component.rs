@@ -15,7 +15,7 @@ use crate::server_query::create_game_template;
 pub fn custom_game_design() -> Html {
     let navigator = use_navigator().expect("Must be used within a Router");
     let language_render_trigger = use_state(|| 0u32);
-    let comment = use_state(|| String::new());
+    let comment = use_state(String::new);
     let form_status = use_state(|| GameTemplateStatus::NotSet);
 
     let comment_ref = use_node_ref();
@@ -95,6 +95,7 @@ pub fn custom_game_design() -> Html {
                     properties: CustomGameInfo {
                         comment: Some(comment_str.clone()),
                     },
+                    model_profile: None,
                 };
 
                 // The checks are handled in oninput callbacks. It should not
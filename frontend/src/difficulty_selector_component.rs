@@ -0,0 +1,38 @@
+use gloo_storage::{LocalStorage, Storage};
+use shared::messages::Difficulty;
+use yew::{function_component, html, use_state, Callback, Html};
+use crate::locale::t;
+
+/// Difficulty of the next new game, remembered in LocalStorage.
+pub fn get_difficulty() -> Difficulty {
+    LocalStorage::get::<String>("difficulty").ok()
+        .and_then(|code| Difficulty::from_code(&code))
+        .unwrap_or_default()
+}
+
+pub fn difficulty_label(difficulty: Difficulty) -> String {
+    t(&format!("difficulty.{}", difficulty.to_code()))
+}
+
+#[function_component(DifficultySelector)]
+pub fn difficulty_selector() -> Html {
+    let current = use_state(get_difficulty);
+
+    html! {
+        <div class="difficulty-selector" title={t("ui.difficulty")}>
+            { for Difficulty::ALL.iter().map(|&difficulty| {
+                let current = current.clone();
+                let class = if *current == difficulty { "difficulty-button active" } else { "difficulty-button" };
+                let onclick = Callback::from(move |_| {
+                    if LocalStorage::set("difficulty", difficulty.to_code()).is_err() {
+                        log::error!("Failed to save difficulty to localStorage");
+                    }
+                    current.set(difficulty);
+                });
+                html! {
+                    <button {class} {onclick}>{ difficulty_label(difficulty) }</button>
+                }
+            }) }
+        </div>
+    }
+}
@@ -4,14 +4,15 @@ use gloo_storage::{LocalStorage, Storage};
 use log::info;
 use yew::{function_component, html, use_effect_with, use_reducer, use_state, Callback, Html};
 use crate::Route;
+use yew_router::components::Link;
 use yew_router::hooks::use_navigator;
-use crate::server_query::{fetch_new_game_token, fetch_text, send_question};
+use crate::server_query::{fetch_new_game_token, fetch_spectator_token, fetch_text, game_state_path, send_question};
 use crate::ask_prompt_component::AskPrompt;
 use crate::board_component::{Act, Board, BoardState};
 use crate::locale::{t, get_current_language};
 use crate::language_selector_component::LanguageSelector;
 use wasm_bindgen_futures::spawn_local;
-use shared::messages::{GameState, ServerResponse, Status};
+use shared::messages::{ErrorResponse, GameState, ServerResponse, Status};
 use shared::gpt::check_question;
 
 
@@ -27,6 +28,7 @@ pub fn Game() -> Html {
     let show_instructions = use_state(|| true);
     let language_render_trigger = use_state(|| 0u32);
     let board = use_reducer(BoardState::default);
+    let spectator_token = use_state(|| None::<String>);
 
     let (token, has_token) = match LocalStorage::get::<String>("token") {
         Ok(token) => (token, true),
@@ -38,7 +40,7 @@ pub fn Game() -> Html {
         let version = version.clone();
         let pending = pending.clone();
         Callback::from(move |text: String| {
-            if !check_question(&text).is_ok() {
+            if check_question(&text).is_err() {
                 return
             }
             let (token, version, pending) = (token.clone(), version.clone(), pending.clone());
@@ -47,6 +49,7 @@ pub fn Game() -> Html {
 
                 if let Err(e) = send_question(&token, &text).await {
                     info!("Error sending question: {:?}", e);
+                    pending.set(false);
                     return;
                 }
                 version.set(*version + 1);
@@ -85,7 +88,7 @@ pub fn Game() -> Html {
                         break; 
                     }
                     
-                    let url = format!("/api/game/{token}?wait={wait}&quiet={quiet}");
+                    let url = format!("{}?wait={wait}&quiet={quiet}", game_state_path(&token));
                     match fetch_text(&url).await {
                         Ok(res) => {
                             match ServerResponse::<GameState>::from_response(&res) {
@@ -125,7 +128,12 @@ pub fn Game() -> Html {
                         Err(e) => {
                             log::error!("Failed to fetch game state: {e:?}");
                             active_game.set(false);
-                            board.dispatch(Act::InvalidGame);
+                            // Keep the board unless the server tells the game is gone
+                            let keep_game = e.downcast_ref::<ErrorResponse>()
+                                .is_some_and(|err| !err.invalid_token);
+                            if !keep_game {
+                                board.dispatch(Act::InvalidGame);
+                            }
                             break;
                         }
                     }
@@ -137,10 +145,26 @@ pub fn Game() -> Html {
         }
     });
 
+    let on_share = {
+        let token = token.clone();
+        let spectator_token = spectator_token.clone();
+        Callback::from(move |_| {
+            let (token, spectator_token) = (token.clone(), spectator_token.clone());
+            spawn_local(async move {
+                match fetch_spectator_token(&token).await {
+                    Ok(spectator) => spectator_token.set(Some(spectator)),
+                    Err(e) => log::error!("Failed to get spectator token: {e:?}"),
+                }
+            });
+        })
+    };
+
     let on_new_game = {
         let version = version.clone();
         let navigator = navigator.clone();
+        let spectator_token = spectator_token.clone();
         Callback::from(move |_| {
+            spectator_token.set(None);
             let (version, navigator) = (version.clone(), navigator.clone());
             spawn_local(async move {
                 info!("Creating new game, lang={}", get_current_language().to_code());
@@ -204,7 +228,7 @@ pub fn Game() -> Html {
         <>
             <h1>{t("ui.game_header")}</h1>
 
-            <Board board={board.clone()} on_new_game={on_new_game} on_custom_game={on_custom_game} />
+            <Board board={board.clone()} on_new_game={on_new_game} on_custom_game={on_custom_game} token={has_token.then(|| token.clone())} />
 
             if *active_game {
                 <AskPrompt 
@@ -213,6 +237,13 @@ pub fn Game() -> Html {
                     disabled={*pending}
                     token={Some(token.clone())}
                 />
+                <div class="spectate-share">
+                    if let Some(spectator) = &*spectator_token {
+                        <Link<Route> to={Route::Watch { token: spectator.clone() }}>{ t("spectate.link") }</Link<Route>>
+                    } else {
+                        <button class="new-game" onclick={on_share}>{ t("spectate.share") }</button>
+                    }
+                </div>
             }
 
             <div class="instructions-container">
@@ -0,0 +1,98 @@
+use chrono::DateTime;
+use gloo_storage::{LocalStorage, Storage};
+use shared::messages::{GameOutcome, HistoryGame};
+use wasm_bindgen_futures::spawn_local;
+use yew::{function_component, html, use_effect_with, use_state, Callback, Html};
+use yew_router::hooks::use_navigator;
+use crate::difficulty_selector_component::difficulty_label;
+use crate::locale::t;
+use crate::server_query::fetch_history;
+use crate::Route;
+
+fn format_created(created: i64) -> String {
+    DateTime::from_timestamp(created, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn outcome_label(outcome: GameOutcome) -> String {
+    t(&format!("history.outcome_{}", outcome.to_code()))
+}
+
+/// Games of the player session; any of them can be reopened on the board.
+#[function_component(History)]
+pub fn history() -> Html {
+    let navigator = use_navigator().expect("Must be used within a Router");
+    let games = use_state(|| None::<Vec<HistoryGame>>);
+    let failed = use_state(|| false);
+
+    use_effect_with((), {
+        let (games, failed) = (games.clone(), failed.clone());
+        move |_| {
+            spawn_local(async move {
+                match fetch_history().await {
+                    Ok(list) => games.set(Some(list)),
+                    Err(e) => {
+                        log::error!("Failed to fetch the history: {e:?}");
+                        failed.set(true);
+                    }
+                }
+            });
+        }
+    });
+
+    let open = {
+        let navigator = navigator.clone();
+        Callback::from(move |token: String| {
+            if LocalStorage::set("token", &token).is_ok() {
+                navigator.push(&Route::Game);
+            } else {
+                log::error!("Failed to save token to localStorage");
+            }
+        })
+    };
+
+    let on_back = Callback::from(move |_| navigator.push(&Route::Game));
+
+    let content = match &*games {
+        _ if *failed => html! { <p>{ t("history.failed") }</p> },
+        None => html! {},
+        Some(list) if list.is_empty() => html! { <p>{ t("history.empty") }</p> },
+        Some(list) => html! {
+            <table class="history">
+                <tr>
+                    <th>{ t("history.col_started") }</th>
+                    <th>{ t("ui.difficulty") }</th>
+                    <th>{ t("history.col_questions") }</th>
+                    <th>{ t("history.col_outcome") }</th>
+                    <th></th>
+                </tr>
+                { for list.iter().map(|game| {
+                    let onclick = {
+                        let (open, token) = (open.clone(), game.token.clone());
+                        Callback::from(move |_| open.emit(token.clone()))
+                    };
+                    html! {
+                        <tr>
+                            <td>{ format_created(game.created) }</td>
+                            <td>{ difficulty_label(game.difficulty) }</td>
+                            <td>{ game.questions }</td>
+                            <td class={format!("outcome-{}", game.outcome.to_code())}>{ outcome_label(game.outcome) }</td>
+                            <td><button {onclick}>{ t("history.open") }</button></td>
+                        </tr>
+                    }
+                }) }
+            </table>
+        },
+    };
+
+    html! {
+        <>
+            <h1>{ t("history.header") }</h1>
+            { content }
+            <div class="button-row">
+                <button class="new-game" onclick={on_back}>{ t("replay.back") }</button>
+            </div>
+        </>
+    }
+}
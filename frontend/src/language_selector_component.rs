@@ -67,7 +67,7 @@ pub fn language_selector(props: &LanguageSelectorProps) -> Html {
                     }
                     // Clear the token and invalidate the game state if callbacks are provided
                     if board_dispatch.is_some() {
-                        let _ = LocalStorage::delete("token");
+                        LocalStorage::delete("token");
                         if let Some(dispatch) = &board_dispatch {
                             dispatch.emit(Act::InvalidGame);
                         }
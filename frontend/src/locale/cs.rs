@@ -16,6 +16,10 @@ pub fn load(h: &mut TranslationInserter) {
     h.add("game.rule1", "Jsou povoleny pouze otázky, na které lze odpovědět ANO nebo NE.");
     h.add("game.rule2", "Pokud na otázku nelze odpovědět jednoduchým ano/ne, odpověď bude NELZE.");
     h.add("game.rule3", "Napiš: \"KONEC\" a já odhalím svou identitu a vysvětlím své odpovědi.");
+    h.add("game.conflict", "Tato odpověď možná odporuje otázce {}.");
+    h.add("game.difficulty", "Obtížnost: {}");
+    h.add("game.questions_left", "Zbývá otázek: {}");
+    h.add("game.out_of_questions", "Došly otázky, hra je prohraná.");
 
     // UI elements
     h.add("ui.send", "Odeslat");
@@ -32,6 +36,45 @@ pub fn load(h: &mut TranslationInserter) {
     // Language selector
     h.add("ui.language", "Jazyk");
 
+    // Difficulty selector
+    h.add("ui.difficulty", "Obtížnost");
+    h.add("difficulty.easy", "Lehká");
+    h.add("difficulty.normal", "Normální");
+    h.add("difficulty.hard", "Těžká");
+
+    // Transcript export and replay
+    h.add("ui.export", "Stáhnout záznam:");
+    h.add("ui.replay", "Přehrát záznam hry");
+    h.add("replay.header", "Přehrání záznamu");
+    h.add("replay.paste", "Vložte exportovaný JSON záznam");
+    h.add("replay.show", "Zobrazit");
+    h.add("replay.invalid", "Toto není platný záznam hry.");
+    h.add("replay.back", "Zpět ke hře");
+
+    // Spectators
+    h.add("spectate.share", "Nechat ostatní sledovat");
+    h.add("spectate.link", "Odkaz pro diváky – sdílejte ho");
+    h.add("spectate.header", "Sledování hry");
+    h.add("spectate.live", "Živě");
+    h.add("spectate.pending", "Přemýšlím o otázce…");
+    h.add("spectate.ended", "Hra skončila");
+    h.add("spectate.lost", "Hra už není k dispozici");
+
+    // Game history
+    h.add("ui.history", "Moje hry");
+    h.add("history.header", "Moje hry");
+    h.add("history.empty", "Zatím žádné hry.");
+    h.add("history.failed", "Hry se nepodařilo načíst.");
+    h.add("history.col_started", "Začátek");
+    h.add("history.col_questions", "Otázky");
+    h.add("history.col_outcome", "Výsledek");
+    h.add("history.open", "Otevřít");
+    h.add("history.outcome_active", "Probíhá");
+    h.add("history.outcome_solved", "Uhodnuto");
+    h.add("history.outcome_gave_up", "Vzdáno");
+    h.add("history.outcome_out_of_questions", "Došly otázky");
+    h.add("history.outcome_ended", "Ukončeno");
+
     // Confirmation dialog
     h.add("dialog.confirm_language_switch", "Změna jazyka ukončí aktuální hru. Opravdu mám hru ukončit?");
     h.add("dialog.yes", "Ano");
@@ -45,4 +88,33 @@ pub fn load(h: &mut TranslationInserter) {
 
     h.add("custom.cancel_button", "Zrušit");
     h.add("custom.create_button", "Vytvořit");
-}
\ No newline at end of file
+
+    // Admin dashboard
+    h.add("admin.title", "Administrace");
+    h.add("admin.token_label", "Administrátorský token");
+    h.add("admin.sign_in", "Přihlásit");
+    h.add("admin.sign_out", "Odhlásit");
+    h.add("admin.refresh", "Obnovit");
+    h.add("admin.games", "Hry ({})");
+    h.add("admin.templates", "Šablony ({})");
+    h.add("admin.col_token", "Token");
+    h.add("admin.col_lang", "Jazyk");
+    h.add("admin.col_age", "Stáří");
+    h.add("admin.col_questions", "Otázky");
+    h.add("admin.col_state", "Stav");
+    h.add("admin.col_identity", "Identita");
+    h.add("admin.col_assets", "Podklady");
+    h.add("admin.col_variant", "Varianta");
+    h.add("admin.assets", "Verze podkladů {}");
+    h.add("admin.reload_assets", "Znovu načíst podklady");
+    h.add("admin.state_active", "běží");
+    h.add("admin.state_pending", "čeká");
+    h.add("admin.state_ended", "skončila");
+    h.add("admin.inspect", "Detail");
+    h.add("admin.end", "Ukončit");
+    h.add("admin.delete", "Smazat");
+    h.add("admin.identity", "Identita: {}");
+    h.add("admin.template", "Šablona: {}");
+    h.add("admin.comment", "Komentář: {}");
+    h.add("admin.pending_question", "Čekající otázka: {}");
+}
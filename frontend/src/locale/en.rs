@@ -16,6 +16,10 @@ pub fn load(h: &mut TranslationInserter) {
     h.add("game.rule1", "Only the questions that can be answered with YES or NO are allowed.");
     h.add("game.rule2", "If a question cannot be answered with a simple yes/no, the response will be UNABLE.");
     h.add("game.rule3", "Type: \"I'M LOSER\", I'll reveal my identity and explain my answers.");
+    h.add("game.conflict", "This answer may contradict question {}.");
+    h.add("game.difficulty", "Difficulty: {}");
+    h.add("game.questions_left", "Questions left: {}");
+    h.add("game.out_of_questions", "No questions left, the game is lost.");
 
     // UI elements
     h.add("ui.send", "Send");
@@ -32,6 +36,45 @@ pub fn load(h: &mut TranslationInserter) {
     // Language selector
     h.add("ui.language", "Language");
 
+    // Difficulty selector
+    h.add("ui.difficulty", "Difficulty");
+    h.add("difficulty.easy", "Easy");
+    h.add("difficulty.normal", "Normal");
+    h.add("difficulty.hard", "Hard");
+
+    // Transcript export and replay
+    h.add("ui.export", "Download transcript:");
+    h.add("ui.replay", "Replay a transcript");
+    h.add("replay.header", "Transcript replay");
+    h.add("replay.paste", "Paste an exported JSON transcript");
+    h.add("replay.show", "Show");
+    h.add("replay.invalid", "This is not a valid game transcript.");
+    h.add("replay.back", "Back to the game");
+
+    // Spectators
+    h.add("spectate.share", "Let others watch");
+    h.add("spectate.link", "Spectator link – share it");
+    h.add("spectate.header", "Watching a game");
+    h.add("spectate.live", "Live");
+    h.add("spectate.pending", "Thinking about a question…");
+    h.add("spectate.ended", "The game is over");
+    h.add("spectate.lost", "The game is no longer available");
+
+    // Game history
+    h.add("ui.history", "My games");
+    h.add("history.header", "My games");
+    h.add("history.empty", "No games yet.");
+    h.add("history.failed", "The games could not be loaded.");
+    h.add("history.col_started", "Started");
+    h.add("history.col_questions", "Questions");
+    h.add("history.col_outcome", "Outcome");
+    h.add("history.open", "Open");
+    h.add("history.outcome_active", "In progress");
+    h.add("history.outcome_solved", "Solved");
+    h.add("history.outcome_gave_up", "Gave up");
+    h.add("history.outcome_out_of_questions", "Out of questions");
+    h.add("history.outcome_ended", "Ended");
+
     // Confirmation dialog
     h.add("dialog.confirm_language_switch", "Switching languages will end the current game. Do you really want to switch?");
     h.add("dialog.yes", "Yes");
@@ -45,4 +88,33 @@ pub fn load(h: &mut TranslationInserter) {
 
     h.add("custom.cancel_button", "Cancel");
     h.add("custom.create_button", "Create");
-}
\ No newline at end of file
+
+    // Admin dashboard
+    h.add("admin.title", "Admin");
+    h.add("admin.token_label", "Admin token");
+    h.add("admin.sign_in", "Sign in");
+    h.add("admin.sign_out", "Sign out");
+    h.add("admin.refresh", "Refresh");
+    h.add("admin.games", "Games ({})");
+    h.add("admin.templates", "Templates ({})");
+    h.add("admin.col_token", "Token");
+    h.add("admin.col_lang", "Language");
+    h.add("admin.col_age", "Age");
+    h.add("admin.col_questions", "Questions");
+    h.add("admin.col_state", "State");
+    h.add("admin.col_identity", "Identity");
+    h.add("admin.col_assets", "Assets");
+    h.add("admin.col_variant", "Variant");
+    h.add("admin.assets", "Assets version {}");
+    h.add("admin.reload_assets", "Reload assets");
+    h.add("admin.state_active", "active");
+    h.add("admin.state_pending", "pending");
+    h.add("admin.state_ended", "ended");
+    h.add("admin.inspect", "Inspect");
+    h.add("admin.end", "End");
+    h.add("admin.delete", "Delete");
+    h.add("admin.identity", "Identity: {}");
+    h.add("admin.template", "Template: {}");
+    h.add("admin.comment", "Comment: {}");
+    h.add("admin.pending_question", "Pending question: {}");
+}
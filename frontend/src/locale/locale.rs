@@ -23,14 +23,12 @@ impl LocaleManager {
         crate::locale::cs::load(&mut inserter);
         
         // Try to load language from localStorage
-        if let Some(storage_result) = web_sys::window()
+        if let Some(Ok(Some(lang_str))) = web_sys::window()
             .and_then(|w| w.local_storage().ok().flatten())
             .map(|storage| storage.get_item("game_language"))
         {
-            if let Ok(Some(lang_str)) = storage_result {
-                if let Some(lang) = Language::from_str(&lang_str) {
-                    manager.current_language = lang;
-                }
+            if let Some(lang) = Language::from_str(&lang_str) {
+                manager.current_language = lang;
             }
         }
         
@@ -61,7 +59,6 @@ impl LocaleManager {
         self.translations.get(lang, key)
     }
     
-    #[allow(dead_code)]
     pub fn get_formatted(&self, key: &str, args: &[&str]) -> String {
         let template = self.get(key);
         let mut result = template;
@@ -116,7 +113,6 @@ pub fn t_for_language(lang: &Language, key: &str) -> String {
     LOCALE_MANAGER.with(|manager| manager.borrow().get_for_language(lang, key))
 }
 
-#[allow(dead_code)]
 pub fn tf(key: &str, args: &[&str]) -> String {
     LOCALE_MANAGER.with(|manager| manager.borrow().get_formatted(key, args))
 }
@@ -1,5 +1,6 @@
 mod cs;
 mod en;
+#[allow(clippy::module_inception)]
 mod locale;
 
 // Re-export public functions from locale.rs
@@ -1,6 +1,7 @@
 mod ask_prompt_component;
 mod board_component;
 mod custom_game_design_component;
+mod difficulty_selector_component;
 mod game_component;
 mod language_logic;
 mod language_selector_component;
@@ -8,6 +9,10 @@ mod locale;
 mod server_query;
 mod to_html;
 mod apphome_component;
+mod admin_component;
+mod replay_component;
+mod spectate_component;
+mod history_component;
 
 use log::info;
 use yew::prelude::*;
@@ -15,6 +20,10 @@ use yew_router::prelude::*;
 use crate::game_component::Game;
 use crate::custom_game_design_component::CustomGameDesign;
 use crate::apphome_component::AppHome;
+use crate::admin_component::Admin;
+use crate::replay_component::Replay;
+use crate::spectate_component::Spectate;
+use crate::history_component::History;
 //use crate::Route::Home;
 //use crate::server_query::fetch_text;
 
@@ -27,6 +36,14 @@ enum Route {
     Game,
     #[at("/custom-game")]
     CustomGameDesign,
+    #[at("/admin")]
+    Admin,
+    #[at("/replay")]
+    Replay,
+    #[at("/watch/:token")]
+    Watch { token: String },
+    #[at("/history")]
+    History,
     #[at("/error")]
     Error,
     #[not_found]
@@ -40,6 +57,10 @@ fn switch(route: Route) -> Html {
         Route::AppHome => html! { <AppHome /> },
         Route::Game => html! { <Game /> },
         Route::CustomGameDesign => html! { <CustomGameDesign /> },
+        Route::Admin => html! { <Admin /> },
+        Route::Replay => html! { <Replay /> },
+        Route::Watch { token } => html! { <Spectate {token} /> },
+        Route::History => html! { <History /> },
         Route::Error => html! { <Error /> },
         Route::NotFound => html! { <h1>{ "404" }</h1> },
     }
@@ -0,0 +1,96 @@
+use gloo::file::callbacks::{read_as_text, FileReader};
+use gloo::file::File;
+use log::info;
+use shared::messages::Transcript;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::{function_component, html, use_node_ref, use_state, Callback, Html};
+use yew_router::hooks::use_navigator;
+use crate::locale::t;
+use crate::to_html::{ToHtmlEx, ToHtmlExArgs};
+use crate::Route;
+
+/// Read-only board of an exported JSON transcript.
+#[function_component(Replay)]
+pub fn replay() -> Html {
+    let navigator = use_navigator().expect("Must be used within a Router");
+    let transcript = use_state(|| None::<Transcript>);
+    let error = use_state(|| false);
+    // The read is cancelled when the reader is dropped
+    let reader = use_state(|| None::<FileReader>);
+    let text_ref = use_node_ref();
+
+    let show = {
+        let (transcript, error) = (transcript.clone(), error.clone());
+        Callback::from(move |json: String| {
+            match Transcript::parse(&json) {
+                Ok(parsed) => {
+                    transcript.set(Some(parsed));
+                    error.set(false);
+                }
+                Err(e) => {
+                    info!("invalid transcript: {}", e);
+                    transcript.set(None);
+                    error.set(true);
+                }
+            }
+        })
+    };
+
+    let on_file = {
+        let (show, reader) = (show.clone(), reader.clone());
+        Callback::from(move |e: web_sys::Event| {
+            let Some(input) = e.target().and_then(|t| t.dyn_into::<HtmlInputElement>().ok()) else {
+                return;
+            };
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            let show = show.clone();
+            reader.set(Some(read_as_text(&File::from(file), move |result| {
+                show.emit(result.unwrap_or_default());
+            })));
+        })
+    };
+
+    let on_paste = {
+        let (show, text_ref) = (show.clone(), text_ref.clone());
+        Callback::from(move |_| {
+            if let Some(textarea) = text_ref.cast::<HtmlTextAreaElement>() {
+                show.emit(textarea.value());
+            }
+        })
+    };
+
+    let on_back = Callback::from(move |_| navigator.push(&Route::Game));
+
+    let board = match &*transcript {
+        Some(transcript) => {
+            let state = transcript.to_game_state();
+            html! {
+                <div class="board">
+                    { state.to_html(&ToHtmlExArgs { state: &state }) }
+                </div>
+            }
+        }
+        None => html! {},
+    };
+
+    html! {
+        <>
+            <h1>{ t("replay.header") }</h1>
+            <div class="replay-import">
+                <input type="file" accept=".json,application/json" onchange={on_file} />
+                <textarea ref={text_ref} rows="4" placeholder={t("replay.paste")}></textarea>
+                <div class="button-row">
+                    <button class="new-game" onclick={on_paste}>{ t("replay.show") }</button>
+                    <button class="new-game" onclick={on_back}>{ t("replay.back") }</button>
+                </div>
+                if *error {
+                    <div class="replay-error">{ t("replay.invalid") }</div>
+                }
+            </div>
+            { board }
+        </>
+    }
+}
@@ -1,45 +1,195 @@
-use gloo::net::http::Request;
+use gloo::net::http::{Request, RequestBuilder, Response};
+use gloo_storage::{LocalStorage, Storage};
 use log::info;
-use shared::messages::GameTemplate;
+use serde::{de::DeserializeOwned, Serialize};
+use shared::messages::{
+    AdminAssets, AskRequest, ErrorResponse, GameTemplate, HistoryGame, NewGameRequest, ServerResponse, StatusResponse,
+    TokenResponse, API_V1,
+};
+use crate::difficulty_selector_component::get_difficulty;
 use crate::locale::get_current_language;
 
+/// Lets the server localize its error messages to the UI language.
+fn localized(request: RequestBuilder) -> RequestBuilder {
+    request.header("Accept-Language", get_current_language().to_code())
+}
+
+/// Turns a failed response into an error. It's the server's `ErrorResponse`
+/// when the body carries one, so callers can `downcast_ref` it.
+async fn server_error(what: &str, res: Response) -> anyhow::Error {
+    let status = res.status();
+    match res.json::<ErrorResponse>().await {
+        Ok(err) => {
+            info!("{}: server error {}: {}", what, status, err);
+            anyhow::Error::new(err)
+        }
+        Err(_) => anyhow::anyhow!("{}: server error: {}", what, status),
+    }
+}
+
+/// Fetches the body of a successful response; game state polling also gets
+/// `pending` replies this way.
 pub async fn fetch_text(path: &str) -> anyhow::Result<String> {
     info!("fetching: {}", path);
-    let res = Request::get(path).send().await?;
-    if res.status() != 200 {
-        return Err(anyhow::anyhow!("status[{}]: {}", path, res.status()));
+    let res = localized(Request::get(path)).send().await?;
+    if !res.ok() {
+        return Err(server_error(path, res).await);
     }
     let text = res.text().await?;
     Ok(text)
 }
 
 
-pub async fn send_question(token: &str, text: &str) -> anyhow::Result<String> {
+pub fn game_state_path(token: &str) -> String {
+    format!("{API_V1}/games/{token}")
+}
+
+pub fn transcript_path(token: &str, format: &str) -> String {
+    format!("{API_V1}/games/{token}/transcript?format={format}")
+}
+
+pub fn spectate_path(spectator_token: &str) -> String {
+    format!("{API_V1}/spectate/{spectator_token}")
+}
+
+pub async fn send_question(token: &str, text: &str) -> anyhow::Result<StatusResponse> {
     info!("asking : {}: {}", token, text);
-    let path = format!("/api/game/{token}/ask");
-    let request = Request::post(path.as_str()).body(text.to_string())?;
+    let path = format!("{API_V1}/games/{token}/questions");
+    let request = localized(Request::post(path.as_str()))
+        .json(&AskRequest { question: text.to_string() })?;
     let res = request.send().await?;
-    if res.status() != 200 {
-        return Err(anyhow::anyhow!("ask: server error: {}", res.status()));
+    if !res.ok() {
+        return Err(server_error("ask", res).await);
     }
     info!("asked");
-    Ok(res.text().await?)
+    Ok(res.json().await?)
 }
 
+const SESSION_KEY: &str = "session";
+
+/// Token of the player session, started on the first use.
+async fn session_token() -> anyhow::Result<String> {
+    if let Ok(session) = LocalStorage::get::<String>(SESSION_KEY) {
+        return Ok(session);
+    }
+    let res = localized(Request::post(&format!("{API_V1}/sessions"))).send().await?;
+    if !res.ok() {
+        return Err(server_error("session", res).await);
+    }
+    let session = res.json::<TokenResponse>().await?.token;
+    LocalStorage::set(SESSION_KEY, &session)?;
+    Ok(session)
+}
+
+/// The server forgot the session, e.g. its signing key changed.
+fn is_stale_session(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<ErrorResponse>().is_some_and(|e| e.invalid_token)
+}
+
+async fn new_game(session: Option<String>) -> anyhow::Result<String> {
+    let request = localized(Request::post(&format!("{API_V1}/games")))
+        .json(&NewGameRequest {
+            lang: Some(get_current_language()),
+            difficulty: Some(get_difficulty()),
+            session,
+        })?;
+    let res = request.send().await?;
+    if !res.ok() {
+        return Err(server_error("new_game", res).await);
+    }
+    Ok(res.json::<TokenResponse>().await?.token)
+}
+
+/// New game in the player's session; a stale session is replaced once.
 pub async fn fetch_new_game_token() -> anyhow::Result<String> {
-    let code = get_current_language().to_code();
-    fetch_text(&format!("/api/game/new?lang={}", code)).await
+    match new_game(session_token().await.ok()).await {
+        Err(err) if is_stale_session(&err) => {
+            info!("session rejected, starting a new one");
+            LocalStorage::delete(SESSION_KEY);
+            new_game(session_token().await.ok()).await
+        }
+        result => result,
+    }
+}
+
+/// Games of the player session, the newest first.
+pub async fn fetch_history() -> anyhow::Result<Vec<HistoryGame>> {
+    let Ok(session) = LocalStorage::get::<String>(SESSION_KEY) else {
+        return Ok(Vec::new());
+    };
+    let path = format!("{API_V1}/sessions/{session}/games");
+    let response = match fetch_text(&path).await {
+        Err(err) if is_stale_session(&err) => {
+            LocalStorage::delete(SESSION_KEY);
+            return Ok(Vec::new());
+        }
+        result => ServerResponse::<Vec<HistoryGame>>::from_response(&result?)?,
+    };
+    Ok(response.content.unwrap_or_default())
+}
+
+/// Read-only token of the game for spectators.
+pub async fn fetch_spectator_token(token: &str) -> anyhow::Result<String> {
+    let res = localized(Request::post(&format!("{API_V1}/games/{token}/spectators"))).send().await?;
+    if !res.ok() {
+        return Err(server_error("spectator", res).await);
+    }
+    Ok(res.json::<TokenResponse>().await?.token)
 }
 
 
 pub async fn create_game_template(game_template: &GameTemplate) -> anyhow::Result<String> {
-    let request = Request::post("/api/template/new")
-            .body(serde_json::to_string(game_template)?)?;
+    let request = localized(Request::post(&format!("{API_V1}/templates")))
+            .json(game_template)?;
     let res = request.send().await?;
-    if res.status() != 200 {
-        return Err(anyhow::anyhow!("create_custom_game: server error: {}", res.status()));
+    if !res.ok() {
+        return Err(server_error("create_custom_game", res).await);
     }
-    let text = res.text().await?;
-    info!("created game template: {}", &text);
-    Ok(text)
-}
\ No newline at end of file
+    let token = res.json::<TokenResponse>().await?.token;
+    info!("created game template: {}", &token);
+    Ok(token)
+}
+
+
+fn admin(request: RequestBuilder, admin_token: &str) -> RequestBuilder {
+    localized(request).header("Authorization", &format!("Bearer {admin_token}"))
+}
+
+pub async fn admin_fetch<T: DeserializeOwned + Serialize>(path: &str, admin_token: &str) -> anyhow::Result<T> {
+    let res = admin(Request::get(&format!("/api/admin/{path}")), admin_token).send().await?;
+    if !res.ok() {
+        return Err(server_error(path, res).await);
+    }
+    ServerResponse::<T>::from_response(&res.text().await?)?
+        .content
+        .ok_or_else(|| anyhow::anyhow!("{}: empty response", path))
+}
+
+pub async fn admin_end_game(token: &str, admin_token: &str) -> anyhow::Result<()> {
+    let path = format!("/api/admin/games/{token}/end");
+    let res = admin(Request::post(&path), admin_token).send().await?;
+    if !res.ok() {
+        return Err(server_error(&path, res).await);
+    }
+    Ok(())
+}
+
+pub async fn admin_reload_assets(admin_token: &str) -> anyhow::Result<AdminAssets> {
+    let path = "/api/admin/assets/reload";
+    let res = admin(Request::post(path), admin_token).send().await?;
+    if !res.ok() {
+        return Err(server_error(path, res).await);
+    }
+    ServerResponse::<AdminAssets>::from_response(&res.text().await?)?
+        .content
+        .ok_or_else(|| anyhow::anyhow!("{}: empty response", path))
+}
+
+pub async fn admin_delete_game(token: &str, admin_token: &str) -> anyhow::Result<()> {
+    let path = format!("/api/admin/games/{token}");
+    let res = admin(Request::delete(&path), admin_token).send().await?;
+    if !res.ok() {
+        return Err(server_error(&path, res).await);
+    }
+    Ok(())
+}
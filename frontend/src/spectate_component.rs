@@ -0,0 +1,86 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use log::info;
+use shared::messages::{GameState, ServerResponse, Status};
+use wasm_bindgen_futures::spawn_local;
+use yew::{function_component, html, use_effect_with, use_state, Html, Properties};
+use crate::locale::t;
+use crate::server_query::{fetch_text, spectate_path};
+use crate::to_html::{ToHtmlEx, ToHtmlExArgs};
+
+#[derive(Properties, PartialEq)]
+pub struct SpectateProps {
+    pub token: String,
+}
+
+/// Read-only board following a game live through the spectator token.
+#[function_component(Spectate)]
+pub fn spectate(props: &SpectateProps) -> Html {
+    let game = use_state(|| None::<GameState>);
+    let pending = use_state(|| false);
+    let lost = use_state(|| false);
+
+    use_effect_with(props.token.clone(), {
+        let (game, pending, lost) = (game.clone(), pending.clone(), lost.clone());
+        move |token: &String| {
+            let cancelled = Rc::new(Cell::new(false));
+            let cancel_for_task = cancelled.clone();
+            let token = token.clone();
+
+            spawn_local(async move {
+                let mut wait = 0;
+                while !cancel_for_task.get() {
+                    let url = format!("{}?wait={wait}", spectate_path(&token));
+                    let response = match fetch_text(&url).await {
+                        Ok(res) => ServerResponse::<GameState>::from_response(&res).ok(),
+                        Err(e) => {
+                            info!("Failed to fetch spectated game: {e:?}");
+                            None
+                        }
+                    };
+                    let Some(content) = response.and_then(|r| {
+                        pending.set(r.status == Status::Pending);
+                        r.content
+                    }) else {
+                        lost.set(true);
+                        break;
+                    };
+                    let ended = content.game_ended;
+                    game.set(Some(content));
+                    if ended {
+                        break;
+                    }
+                    wait = 1;
+                }
+            });
+
+            move || cancelled.set(true)
+        }
+    });
+
+    let status = if *lost {
+        t("spectate.lost")
+    } else if game.as_ref().is_some_and(|g| g.game_ended) {
+        t("spectate.ended")
+    } else if *pending {
+        t("spectate.pending")
+    } else {
+        t("spectate.live")
+    };
+
+    let board = match &*game {
+        Some(state) => state.to_html(&ToHtmlExArgs { state }),
+        None => html! {},
+    };
+
+    html! {
+        <>
+            <h1>{ t("spectate.header") }</h1>
+            <div class="spectate-status">{ status }</div>
+            <div class="board">
+                { board }
+            </div>
+        </>
+    }
+}
@@ -1,6 +1,7 @@
 use yew::{html, Html};
 use shared::messages::{Answer, GameState, Question, Record, Verdict};
-use crate::locale::t;
+use crate::difficulty_selector_component::difficulty_label;
+use crate::locale::{t, tf};
 #[derive(Clone, PartialEq)]
 pub struct  ToHtmlExArgs<'a> {
     pub state: &'a GameState,
@@ -56,7 +57,7 @@ impl ToHtmlEx for Answer {
         if args.state.game_ended {
             html! {
                 <div class="comment">
-                    { self.comment.as_deref().unwrap_or(&"".to_string()) }
+                    { self.comment.as_deref().unwrap_or("") }
                 </div>
             }
         } else {
@@ -82,14 +83,35 @@ fn get_verdict_from_record(record: &Record) -> Verdict {
     }
 }
 
+fn conflicts_to_html(record: &Record) -> Html {
+    if record.conflicts_with.is_empty() {
+        return html! {};
+    }
+    let questions = record.conflicts_with.iter()
+        .map(|i| format!("#{}", i + 1))
+        .collect::<Vec<_>>()
+        .join(", ");
+    html! {
+        <div class="conflict-note">
+            { tf("game.conflict", &[&questions]) }
+        </div>
+    }
+}
+
 impl ToHtmlEx for Record {
     fn to_html(&self, args: &ToHtmlExArgs) -> Html {
+        let class = if self.conflicts_with.is_empty() {
+            "record record--two-col"
+        } else {
+            "record record--two-col record--flagged"
+        };
         html! {
-            <div class="record record--two-col">
+            <div class={class}>
                 { get_verdict_from_record(self).to_html(args) }
                 <div class="qa">
                 {self.questions.to_html(args) }
                 {self.answers.to_html(args)}
+                { conflicts_to_html(self) }
                 </div>
             </div>
         }
@@ -100,6 +122,26 @@ fn create_pending_question_record(question: &Question) -> Record {
     Record {
         questions: question.clone(),
         answers: Some(Answer::new_pending()),
+        conflicts_with: vec![],
+        guard_flagged: false,
+    }
+}
+
+fn game_info_to_html(state: &GameState) -> Html {
+    let budget = if state.is_out_of_questions() {
+        html! { <span class="game-budget game-budget--out">{ t("game.out_of_questions") }</span> }
+    } else if let Some(left) = state.questions_left() {
+        html! { <span class="game-budget">{ tf("game.questions_left", &[&left.to_string()]) }</span> }
+    } else {
+        html! {}
+    };
+    html! {
+        <div class="game-info">
+            <span class={format!("difficulty difficulty--{}", state.difficulty.to_code())}>
+                { tf("game.difficulty", &[&difficulty_label(state.difficulty)]) }
+            </span>
+            { budget }
+        </div>
     }
 }
 
@@ -107,11 +149,12 @@ impl ToHtmlEx for GameState {
     fn to_html(&self, args: &ToHtmlExArgs) -> Html {
         html! {
             <div class="game">
+                { game_info_to_html(self) }
                 { for self.records.iter().map(|r| r.to_html(args)) }
                 { if let Some(pendig_question) = &self.pending_question {
 
                     html! {
-                        create_pending_question_record(&pendig_question)
+                        create_pending_question_record(pendig_question)
                         .to_html(args)
                     }
 
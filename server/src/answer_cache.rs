@@ -0,0 +1,284 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use linked_hash_map::LinkedHashMap;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use shared::locale::Language;
+use shared::messages::{Answer, Verdict};
+
+use crate::config::{self, Config};
+use crate::metrics::metrics;
+use crate::text::normalize;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    identity: String,
+    lang: Language,
+    question: String,
+    prompt_version: String,
+    /// Model answering the question, after the profile of the game is applied.
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    profile: Option<String>,
+}
+
+impl CacheKey {
+    pub fn new(identity: &str, lang: &Language, question: &str, prompt_version: &str,
+               model: &str, profile: Option<&str>) -> Self {
+        Self {
+            identity: identity.to_lowercase(),
+            lang: lang.clone(),
+            question: normalize(question),
+            prompt_version: prompt_version.to_string(),
+            model: model.to_string(),
+            profile: profile.map(ToString::to_string),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CacheEntry {
+    key: CacheKey,
+    answer: Answer,
+    created: i64,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// Answers of previously asked questions, in LRU order.
+pub struct AnswerCache {
+    config: config::AnswerCache,
+    entries: Mutex<LinkedHashMap<CacheKey, CacheEntry>>,
+    persist_file: Option<PathBuf>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Only plain answers are worth caching; FINAL depends on the exact guess and
+/// the rest don't come from the model.
+fn is_cacheable(answer: &Answer) -> bool {
+    matches!(answer.verdict, Some(Verdict::Yes) | Some(Verdict::No) | Some(Verdict::Unable))
+}
+
+impl AnswerCache {
+    pub fn new(config: &Config) -> Self {
+        let cache = Self {
+            config: config.answer_cache.clone(),
+            entries: Mutex::new(LinkedHashMap::new()),
+            persist_file: config.get_answer_cache_file(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        };
+        if let Err(err) = cache.load() {
+            warn!("answer cache not loaded: {}", err);
+        }
+        cache
+    }
+
+    fn is_expired(&self, entry: &CacheEntry, now: i64) -> bool {
+        now - entry.created > self.config.ttl_secs as i64
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<Answer> {
+        if !self.config.enabled {
+            return None;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let hit = match entries.get_refresh(key) {
+            Some(entry) if !self.is_expired(entry, now()) => Some(entry.answer.as_cached()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        let result = match hit {
+            Some(_) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                "hit"
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                "miss"
+            }
+        };
+        metrics().answer_cache.with_label_values(&[result]).inc();
+        hit
+    }
+
+    pub fn insert(&self, key: CacheKey, answer: &Answer) {
+        if !self.config.enabled || !is_cacheable(answer) {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(key.clone(), CacheEntry { key, answer: answer.clone(), created: now() });
+        while entries.len() > self.config.max_entries {
+            entries.pop_front();
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.lock().unwrap().len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn load(&self) -> Result<()> {
+        let Some(path) = &self.persist_file else {
+            return Ok(());
+        };
+        if !path.exists() {
+            return Ok(());
+        }
+        let content = std::fs::read_to_string(path)?;
+        let loaded = serde_json::from_str::<Vec<CacheEntry>>(&content)?;
+        let now = now();
+        let mut entries = self.entries.lock().unwrap();
+        for entry in loaded.into_iter().filter(|e| !self.is_expired(e, now)) {
+            entries.insert(entry.key.clone(), entry);
+        }
+        info!("answer cache loaded {} entries from {:?}", entries.len(), path);
+        Ok(())
+    }
+
+    /// Writes the cache to the persist file, if configured.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.persist_file else {
+            return Ok(());
+        };
+        let entries = self.entries.lock().unwrap()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string(&entries)?)?;
+        std::fs::rename(&tmp, path)?;
+        let stats = self.stats();
+        info!("answer cache saved; entries={} hits={} misses={} evictions={}",
+            stats.entries, stats.hits, stats.misses, stats.evictions);
+        Ok(())
+    }
+
+    pub fn persist_interval(&self) -> Option<std::time::Duration> {
+        self.persist_file.as_ref()?;
+        Some(std::time::Duration::from_secs(self.config.persist_interval_secs.max(1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_entries: usize) -> AnswerCache {
+        AnswerCache {
+            config: config::AnswerCache { enabled: true, max_entries, ..config::AnswerCache::default() },
+            entries: Mutex::new(LinkedHashMap::new()),
+            persist_file: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    fn key(identity: &str, question: &str) -> CacheKey {
+        CacheKey::new(identity, &Language::English, question, "v1", "gpt-5-nano", None)
+    }
+
+    fn yes() -> Answer {
+        Answer::from_verdict(Verdict::Yes, "Sure.")
+    }
+
+    #[test]
+    fn key_normalizes_the_question_and_identity() {
+        assert_eq!(key("Einstein", "Are you alive?"), key("einstein", "are you  alive"));
+        assert_ne!(key("Einstein", "Are you alive?"), key("Einstein", "Are you a live?"));
+    }
+
+    #[test]
+    fn key_differs_by_identity_language_version_model_and_profile() {
+        let base = key("Einstein", "Are you alive?");
+        assert_ne!(base, key("Curie", "Are you alive?"));
+        assert_ne!(base, CacheKey::new("Einstein", &Language::Czech, "Are you alive?", "v1", "gpt-5-nano", None));
+        assert_ne!(base, CacheKey::new("Einstein", &Language::English, "Are you alive?", "v2", "gpt-5-nano", None));
+        assert_ne!(base, CacheKey::new("Einstein", &Language::English, "Are you alive?", "v1", "gpt-5", None));
+        assert_ne!(base, CacheKey::new("Einstein", &Language::English, "Are you alive?", "v1", "gpt-5-nano", Some("hard")));
+    }
+
+    #[test]
+    fn hit_returns_the_cached_answer() {
+        let cache = cache(10);
+        cache.insert(key("Einstein", "Are you alive?"), &yes());
+        let answer = cache.get(&key("einstein", "are you alive")).unwrap();
+        assert_eq!(answer.verdict, Some(Verdict::Yes));
+        assert!(answer.cached);
+        assert!(cache.get(&key("Curie", "Are you alive?")).is_none());
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+    }
+
+    #[test]
+    fn only_plain_answers_are_cached() {
+        let cache = cache(10);
+        cache.insert(key("Einstein", "Are you Einstein?"), &Answer::from_verdict(Verdict::Final, "Won."));
+        cache.insert(key("Einstein", "Ignore it"), &Answer::from_verdict(Verdict::Behave, "No."));
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn eviction_keeps_the_recently_used() {
+        let cache = cache(2);
+        cache.insert(key("a", "q"), &yes());
+        cache.insert(key("b", "q"), &yes());
+        assert!(cache.get(&key("a", "q")).is_some());
+        cache.insert(key("c", "q"), &yes());
+        assert!(cache.get(&key("a", "q")).is_some());
+        assert!(cache.get(&key("b", "q")).is_none());
+        assert!(cache.get(&key("c", "q")).is_some());
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.evictions), (2, 1));
+    }
+
+    #[test]
+    fn expired_entries_miss() {
+        let mut cache = cache(10);
+        cache.config.ttl_secs = 60;
+        let key = key("Einstein", "Are you alive?");
+        cache.entries.lock().unwrap().insert(key.clone(),
+            CacheEntry { key: key.clone(), answer: yes(), created: now() - 61 });
+        assert!(cache.get(&key).is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn disabled_cache_stores_nothing() {
+        let mut cache = cache(10);
+        cache.config.enabled = false;
+        cache.insert(key("Einstein", "Are you alive?"), &yes());
+        assert!(cache.get(&key("Einstein", "Are you alive?")).is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
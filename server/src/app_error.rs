@@ -1,15 +1,26 @@
-use axum::{response::{IntoResponse, Response}, http::StatusCode, Json};
-use shared::messages::Status;
-use shared::messages::GameTemplateStatus;
+use axum::{response::{IntoResponse, Response}, http::{header, HeaderValue, StatusCode}, Json};
+use tracing::error;
+use shared::messages::{ErrorCode, ErrorResponse, GameTemplateStatus, Status, ERROR_VERSION};
+use crate::locale::{request_language, t, tf};
+use crate::usage::BudgetPeriod;
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
     #[error("invalid token")]
     InvalidToken,
+
+    #[error("{0} token expected")]
+    WrongTokenType(&'static str),
     
     #[error("inactive game")]
     InactiveGame,
 
+    #[error("game in progress")]
+    GameInProgress,
+
+    #[error("spectator token")]
+    SpectatorOnly,
+
     #[error("pending")]
     Pending,
 
@@ -22,6 +33,11 @@ pub enum AppError {
     #[error(transparent)]
     Any(#[from] anyhow::Error),
 
+    /// The request body isn't JSON of the expected shape.
+    #[error("invalid request body: {0}")]
+    InvalidJson(serde_json::Error),
+
+    /// Serialization failed; request bodies are `InvalidJson`.
     #[error("internal server error")]
     JsonError(#[from] serde_json::Error),
 
@@ -33,28 +49,118 @@ pub enum AppError {
 
     #[error("invalid game template")]
     InvalidGameTemplate(GameTemplateStatus),
+
+    #[error("{0:?} budget exhausted, try again later")]
+    BudgetExceeded(BudgetPeriod),
+
+    #[error("unauthorized")]
+    Unauthorized,
+
+    #[error("too many requests, retry after {0}s")]
+    RateLimited(u64),
+
+    #[error("not found")]
+    NotFound,
+
+    #[error("server busy")]
+    ServerBusy,
+}
+
+impl AppError {
+    fn code(&self) -> ErrorCode {
+        match self {
+            AppError::InvalidToken => ErrorCode::InvalidToken,
+            AppError::WrongTokenType(_) => ErrorCode::WrongTokenType,
+            AppError::InactiveGame => ErrorCode::InactiveGame,
+            AppError::GameInProgress => ErrorCode::GameInProgress,
+            AppError::SpectatorOnly => ErrorCode::SpectatorOnly,
+            AppError::Pending => ErrorCode::Pending,
+            AppError::GameNotFound => ErrorCode::GameNotFound,
+            AppError::InvalidInput | AppError::InvalidJson(_) => ErrorCode::InvalidInput,
+            AppError::Any(_) | AppError::JsonError(_) | AppError::InternalServerError => ErrorCode::InternalServerError,
+            AppError::Timeout => ErrorCode::Timeout,
+            AppError::InvalidGameTemplate(_) => ErrorCode::InvalidGameTemplate,
+            AppError::BudgetExceeded(_) => ErrorCode::BudgetExceeded,
+            AppError::Unauthorized => ErrorCode::Unauthorized,
+            AppError::RateLimited(_) => ErrorCode::RateLimited,
+            AppError::NotFound => ErrorCode::NotFound,
+            AppError::ServerBusy => ErrorCode::ServerBusy,
+        }
+    }
+
+    fn http_status(&self) -> StatusCode {
+        match self {
+            // Not failures; the client keeps polling
+            AppError::Pending | AppError::Timeout => StatusCode::OK,
+            AppError::InvalidToken | AppError::WrongTokenType(_) | AppError::InvalidInput | AppError::InvalidJson(_) => StatusCode::BAD_REQUEST,
+            AppError::GameNotFound | AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::InactiveGame | AppError::GameInProgress => StatusCode::CONFLICT,
+            AppError::InvalidGameTemplate(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::SpectatorOnly => StatusCode::FORBIDDEN,
+            AppError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::BudgetExceeded(_) | AppError::ServerBusy => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Any(_) | AppError::JsonError(_) | AppError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let http_status = StatusCode::OK;
         let status = match self {
-            AppError::Pending => Status::Pending,
-            AppError::Timeout => Status::Pending,
+            AppError::Pending | AppError::Timeout => Status::Pending,
             _ => Status::Error
         };
+        let code = self.code();
+        let http_status = self.http_status();
+        if http_status.is_server_error() {
+            error!("request failed: {}", self);
+        }
+
+        let lang = request_language();
+        let retry_after = match self {
+            AppError::RateLimited(retry_after) => Some(retry_after),
+            _ => None,
+        };
+        let message = match retry_after {
+            Some(secs) => tf(&lang, code.locale_key(), &[&secs.to_string()]),
+            None => t(&lang, code.locale_key()),
+        };
 
-        let invalid_token = match self {
-            AppError::InvalidToken => true,
-            AppError::GameNotFound => true,
-            _ => false
+        let body = ErrorResponse {
+            status,
+            version: ERROR_VERSION,
+            code,
+            message,
+            invalid_token: matches!(self, AppError::InvalidToken | AppError::WrongTokenType(_) | AppError::GameNotFound),
+            retry_after,
         };
 
-        let body = serde_json::json!({
-            "status": status,
-            "invalid_token": invalid_token,
-            "message": self.to_string()
-        });
-        (http_status, Json(body)).into_response()
+        let mut response = (http_status, Json(body)).into_response();
+        if let Some(secs) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_request_body_is_client_error() {
+        let err = serde_json::from_slice::<shared::messages::AskRequest>(b"{\"q\": 1").unwrap_err();
+        let err = AppError::InvalidJson(err);
+        assert_eq!(err.http_status(), StatusCode::BAD_REQUEST);
+        assert_eq!(err.code(), ErrorCode::InvalidInput);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn serialization_failure_is_server_error() {
+        let map = std::collections::HashMap::from([((1, 2), 3)]);
+        let err = AppError::from(serde_json::to_string(&map).unwrap_err());
+        assert_eq!(err.http_status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(err.code(), ErrorCode::InternalServerError);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use anyhow::{bail, Context, Result};
+use rand::seq::IndexedRandom;
+use shared::locale::Language;
+use tracing::info;
+
+use crate::config::{Config, PromptVariant};
+use crate::game_prompt::prompt_version;
+use crate::locale::LocaleManager;
+use crate::prompt_template::Template;
+
+/// Files of the assets directory which can be changed without a restart: the
+/// instructions, the identity catalogs and the translation overrides. A reload
+/// swaps all of them at once; running games keep the assets they started with.
+#[derive(Debug)]
+pub struct Assets {
+    /// Fingerprint of the contents, recorded by every game.
+    pub version: String,
+    variants: Vec<Variant>,
+    pub locale: LocaleManager,
+}
+
+/// Prompt variant with its instructions template per language.
+#[derive(Debug)]
+pub struct Variant {
+    pub name: String,
+    pub weight: u32,
+    instructions: HashMap<Language, Template>,
+}
+
+fn load_instructions(config: &Config, file: &str, lang: &Language) -> Result<Template> {
+    let path = config.get_instructions_file(file, lang);
+    let source = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read instructions {:?}", path))?;
+    if source.trim().is_empty() {
+        bail!("Instructions {:?} are empty", path);
+    }
+    let mut include = |name: &str| {
+        let path = config.get_include_file(name);
+        fs::read_to_string(&path).with_context(|| format!("Failed to read included {:?}", path))
+    };
+    Template::parse(&path.display().to_string(), &source, &mut include)
+}
+
+impl Assets {
+    pub fn load(config: &Config) -> Result<Self> {
+        let locale = LocaleManager::load(config)?;
+        let mut variants = Vec::new();
+        let mut contents = String::new();
+        for PromptVariant { name, instructions_file, weight } in config.get_prompt_variants() {
+            let mut instructions = HashMap::new();
+            contents.push_str(&format!("{}\0{}\0", name, weight));
+            for lang in locale.available_languages() {
+                let template = load_instructions(config, &instructions_file, &lang)?;
+                contents.push_str(template.source());
+                instructions.insert(lang, template);
+            }
+            variants.push(Variant { name, weight, instructions });
+        }
+        if variants.iter().all(|v| v.weight == 0) {
+            bail!("No prompt variant with a positive weight");
+        }
+        for lang in locale.available_languages() {
+            contents.push_str(&locale.contents(&lang));
+        }
+
+        Ok(Self {
+            version: prompt_version(&contents)[..8].to_string(),
+            variants,
+            locale,
+        })
+    }
+
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    /// Variant of a new game, picked by the weights.
+    pub fn pick_variant(&self) -> &str {
+        self.variants.choose_weighted(&mut rand::rng(), |v| v.weight)
+            .map_or(self.variants[0].name.as_str(), |v| v.name.as_str())
+    }
+
+    /// Instructions template of the variant and the language; the first
+    /// variant stands in for unknown ones, the default language for missing
+    /// ones.
+    pub fn instructions(&self, variant: Option<&str>, lang: &Language) -> &Template {
+        let variant = self.variants.iter()
+            .find(|v| Some(v.name.as_str()) == variant)
+            .unwrap_or(&self.variants[0]);
+        variant.instructions.get(lang)
+            .or_else(|| variant.instructions.get(&Language::default()))
+            .or_else(|| variant.instructions.values().next())
+            .expect("Prompt variant without instructions")
+    }
+
+    pub fn identities_count(&self, lang: &Language) -> usize {
+        self.locale.get_identities(lang).map_or(0, |i| i.list.len())
+    }
+}
+
+/// Config of the repository assets with the given prompt variants, all of
+/// them on `instructions.txt`.
+#[cfg(test)]
+fn test_config(variants: &[(&str, u32)]) -> Config {
+    let variants = variants.iter()
+        .map(|(name, weight)| format!(
+            "[[gpt.variants]]\nname = \"{}\"\ninstructions_file = \"instructions.txt\"\nweight = {}\n", name, weight))
+        .collect::<String>();
+    toml::from_str(&format!(
+        "debug = false\n[www]\nport = 3000\n[gpt]\nmax_clients_count = 1\n{}[dirs]\npkg_assets = {:?}\n",
+        variants, concat!(env!("CARGO_MANIFEST_DIR"), "/assets"))).unwrap()
+}
+
+#[cfg(test)]
+pub fn test_assets(variants: &[(&str, u32)]) -> Assets {
+    Assets::load(&test_config(variants)).unwrap()
+}
+
+static ASSETS: OnceLock<RwLock<Arc<Assets>>> = OnceLock::new();
+
+pub fn init_assets(config: &Config) -> Result<()> {
+    let assets = Assets::load(config)?;
+    info!("assets version {} loaded", assets.version);
+    let _ = ASSETS.set(RwLock::new(Arc::new(assets)));
+    Ok(())
+}
+
+/// Assets new games start with.
+pub fn current() -> Arc<Assets> {
+    ASSETS.get()
+        .expect("Assets not initialized. Call init_assets() first.")
+        .read().unwrap()
+        .clone()
+}
+
+/// Re-reads the assets; the current ones stay when any file is broken.
+pub fn reload(config: &Config) -> Result<Arc<Assets>> {
+    let assets = Arc::new(Assets::load(config)?);
+    let lock = ASSETS.get().expect("Assets not initialized. Call init_assets() first.");
+    let previous = std::mem::replace(&mut *lock.write().unwrap(), assets.clone());
+    info!("assets reloaded, version {} -> {}", previous.version, assets.version);
+    Ok(assets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picks(assets: &Assets, name: &str, count: usize) -> usize {
+        (0..count).filter(|_| assets.pick_variant() == name).count()
+    }
+
+    #[test]
+    fn variants_are_picked_by_weight() {
+        let assets = test_assets(&[("a", 3), ("b", 1)]);
+        let a = picks(&assets, "a", 4000);
+        assert!((2700..3300).contains(&a), "a picked {} times of 4000", a);
+
+        let assets = test_assets(&[("off", 0), ("on", 1)]);
+        assert_eq!(picks(&assets, "on", 200), 200);
+    }
+
+    #[test]
+    fn variants_without_weight_are_refused() {
+        assert!(Assets::load(&test_config(&[("off", 0)])).is_err());
+    }
+
+    #[test]
+    fn unknown_variant_falls_back_to_the_first() {
+        let assets = test_assets(&[("a", 1), ("b", 1)]);
+        let first = assets.instructions(Some("a"), &Language::English);
+        assert!(std::ptr::eq(assets.instructions(Some("gone"), &Language::English), first));
+        assert!(std::ptr::eq(assets.instructions(None, &Language::English), first));
+        assert!(!std::ptr::eq(assets.instructions(Some("b"), &Language::English), first));
+    }
+
+    #[test]
+    fn missing_language_falls_back_to_the_default() {
+        let mut assets = test_assets(&[("a", 1)]);
+        assets.variants[0].instructions.remove(&Language::Czech);
+        let default = assets.instructions(None, &Language::default());
+        assert!(std::ptr::eq(assets.instructions(None, &Language::Czech), default));
+
+        assets.variants[0].instructions.remove(&Language::default());
+        assets.variants[0].instructions.insert(Language::Czech, Template::parse("cs", "cs", &mut |_| unreachable!()).unwrap());
+        assert_eq!(assets.instructions(None, &Language::English).source(), "cs");
+    }
+}
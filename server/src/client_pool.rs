@@ -1,8 +1,12 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
 
-use tokio::sync::Notify;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, info, warn};
 
-use crate::{app_error::AppError, config};
+use crate::{app_error::AppError, config, metrics::metrics};
 
 pub trait PollableClientFactory<Client>: Send + Sync {
     fn build_client(&self) -> Client;
@@ -11,53 +15,34 @@ pub trait PollableClientFactory<Client>: Send + Sync {
 
 pub type Factory<Client> = Arc<dyn PollableClientFactory<Client> + Send + Sync>;
 
-struct ClientsStorage<Client> {
-    clients: Vec<Arc<Client>>,
-    clients_total: u32,
-    notify: Arc<Notify>,
-}
-
-impl<Client> ClientsStorage<Client> {
-    fn new() -> Self {
-        Self {
-            clients: Vec::new(),
-            clients_total: 0,
-            notify: Arc::new(Notify::new()),
-        }
-    }
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStats {
+    pub clients_total: usize,
+    pub idle: usize,
+    pub waiters: usize,
 }
 
+/// Concurrency limiter handing out at most `max_clients_count` clients.
+/// Waiters are served in FIFO order (the tokio semaphore is fair), the queue
+/// is capped by `queue_limit` and a wait gives up after `queue_timeout_secs`.
 pub struct ClientsPool<Client> {
-    storage: StdMutex<ClientsStorage<Client>>,
+    idle: StdMutex<Vec<Arc<Client>>>,
+    clients_total: AtomicUsize,
+    waiters: AtomicUsize,
+    permits: Arc<Semaphore>,
     factory: Factory<Client>,
 }
 
-
 pub struct ClientGuard<Client> {
     client: Option<Arc<Client>>,
     pool: Arc<ClientsPool<Client>>,
-    notify: Arc<Notify>,
+    _permit: OwnedSemaphorePermit,
 }
 
 impl<Client> ClientGuard<Client> {
     pub fn client(&self) -> &Client {
         self.client.as_ref().unwrap().as_ref()
     }
-    pub fn has_client(&self) -> bool {
-        self.client.is_some()
-    }
-    pub async fn update(&mut self) -> Result<(), AppError> {
-        if self.client.is_some() {
-            return Ok(());
-        }
-        loop {
-            self.notify.notified().await;
-            self.client = self.pool.raw_client();
-            if self.has_client() {
-                return Ok(());
-            }
-        }
-    }
 }
 
 impl<Client> Drop for ClientGuard<Client> {
@@ -70,52 +55,181 @@ impl<Client> Drop for ClientGuard<Client> {
 
 impl<Client> ClientsPool<Client> {
     pub fn new(factory: Factory<Client>) -> Self {
+        let max_clients = factory.get_config().max_clients_count as usize;
         Self {
-            storage: StdMutex::new(ClientsStorage::<Client>::new()),
+            idle: StdMutex::new(Vec::new()),
+            clients_total: AtomicUsize::new(0),
+            waiters: AtomicUsize::new(0),
+            permits: Arc::new(Semaphore::new(max_clients)),
             factory,
         }
     }
 
-    pub fn pop(self: &Arc<Self>) -> ClientGuard<Client> {
+    /// Waits for a free client. Fails with `ServerBusy` when the queue is full
+    /// or the wait times out, and with `GameNotFound` once `cancelled` resolves.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        cancelled: impl Future<Output = ()>,
+    ) -> Result<ClientGuard<Client>, AppError> {
+        let permit = match self.permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => self.wait_for_permit(cancelled).await?,
+        };
+
+        let client = self.idle.lock().unwrap().pop();
+        let client = client.unwrap_or_else(|| {
+            let total = self.clients_total.fetch_add(1, Ordering::Relaxed) + 1;
+            debug!("creating client {}", total);
+            Arc::new(self.factory.build_client())
+        });
+
+        Ok(ClientGuard {
+            client: Some(client),
+            pool: Arc::clone(self),
+            _permit: permit,
+        })
+    }
+
+    async fn wait_for_permit(
+        &self,
+        cancelled: impl Future<Output = ()>,
+    ) -> Result<OwnedSemaphorePermit, AppError> {
         let config = self.factory.get_config();
-        let mut storage = self.storage.lock().unwrap();
-        if storage.clients.is_empty() {
-            if storage.clients_total >= config.max_clients_count {
-                return ClientGuard {
-                    client: None,
-                    pool: Arc::clone(self),
-                    notify: storage.notify.clone(),
-                };
-            }
-            storage.clients_total += 1;
-            println!("Creating client {}", storage.clients_total);
-            ClientGuard {
-                client: Some(self.create_client()),
-                pool: Arc::clone(self),
-                notify: storage.notify.clone(),
-            }
-        } else {
-            println!("Using free client; there are {}", storage.clients.len());
-            let client = storage.clients.pop().unwrap();
-            ClientGuard { 
-                client: Some(client), 
-                pool: Arc::clone(self), 
-                notify: storage.notify.clone(),
+        let waiting = self.waiters.fetch_add(1, Ordering::Relaxed);
+        let _waiting = scopeguard::guard((), |_| {
+            self.waiters.fetch_sub(1, Ordering::Relaxed);
+        });
+
+        if waiting >= config.queue_limit {
+            warn!("client pool queue is full ({} waiting)", waiting);
+            metrics().pool_rejected.with_label_values(&["queue_full"]).inc();
+            return Err(AppError::ServerBusy);
+        }
+
+        info!("waiting for a client; {} ahead", waiting);
+        let timeout = Duration::from_secs(config.queue_timeout_secs);
+        tokio::select! {
+            permit = tokio::time::timeout(timeout, self.permits.clone().acquire_owned()) => match permit {
+                Ok(permit) => permit.map_err(|_| AppError::InternalServerError),
+                Err(_) => {
+                    warn!("no client available within {:?}", timeout);
+                    metrics().pool_rejected.with_label_values(&["timeout"]).inc();
+                    Err(AppError::ServerBusy)
+                }
+            },
+            _ = cancelled => {
+                info!("waiting for a client cancelled");
+                Err(AppError::GameNotFound)
             }
         }
     }
 
-    pub fn return_client(&self, client: Arc<Client>) {
-        if let Ok(mut storage) = self.storage.lock() {
-            storage.clients.push(client);
-            storage.notify.notify_one();
+    fn return_client(&self, client: Arc<Client>) {
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.push(client);
         }
     }
-    fn raw_client(&self) -> Option<Arc<Client>> {
-        self.storage.lock().ok()?.clients.pop()
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            clients_total: self.clients_total.load(Ordering::Relaxed),
+            idle: self.idle.lock().map(|idle| idle.len()).unwrap_or_default(),
+            waiters: self.waiters.load(Ordering::Relaxed),
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestFactory {
+        config: config::Gpt,
+    }
+
+    impl PollableClientFactory<()> for TestFactory {
+        fn build_client(&self) {}
+
+        fn get_config(&self) -> &config::Gpt {
+            &self.config
+        }
+    }
+
+    fn pool(max_clients_count: u32, queue_limit: usize) -> Arc<ClientsPool<()>> {
+        let config = toml::from_str(&format!(
+            "max_clients_count = {}\nqueue_limit = {}\nqueue_timeout_secs = 5", max_clients_count, queue_limit)).unwrap();
+        Arc::new(ClientsPool::new(Arc::new(TestFactory { config })))
+    }
+
+    fn never() -> impl Future<Output = ()> {
+        std::future::pending()
+    }
+
+    #[tokio::test]
+    async fn clients_are_reused() {
+        let pool = pool(2, 1);
+        drop(pool.acquire(never()).await.unwrap());
+        let _first = pool.acquire(never()).await.unwrap();
+        let _second = pool.acquire(never()).await.unwrap();
+        let stats = pool.stats();
+        assert_eq!((stats.clients_total, stats.idle), (2, 0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn full_queue_is_busy() {
+        let pool = pool(1, 1);
+        let _busy = pool.acquire(never()).await.unwrap();
+        let waiter = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.acquire(never()).await.map(|_| ()) }
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(pool.stats().waiters, 1);
+        assert!(matches!(pool.acquire(never()).await, Err(AppError::ServerBusy)));
+        assert_eq!(pool.stats().waiters, 1);
+        waiter.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wait_times_out() {
+        let pool = pool(1, 1);
+        let _busy = pool.acquire(never()).await.unwrap();
+        let started = tokio::time::Instant::now();
+        assert!(matches!(pool.acquire(never()).await, Err(AppError::ServerBusy)));
+        assert!(started.elapsed() >= Duration::from_secs(5));
+        assert_eq!(pool.stats().waiters, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waiter_gets_the_released_client() {
+        let pool = pool(1, 1);
+        let busy = pool.acquire(never()).await.unwrap();
+        let waiter = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.acquire(never()).await.map(|_| ()) }
+        });
+        tokio::task::yield_now().await;
+        drop(busy);
+        assert!(waiter.await.unwrap().is_ok());
+        assert_eq!(pool.stats().clients_total, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_wait_takes_no_permit() {
+        let pool = pool(1, 1);
+        let busy = pool.acquire(never()).await.unwrap();
+        let (cancel, cancelled) = tokio::sync::oneshot::channel::<()>();
+        let waiter = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.acquire(async { let _ = cancelled.await; }).await.map(|_| ()) }
+        });
+        tokio::task::yield_now().await;
+        cancel.send(()).unwrap();
+        assert!(matches!(waiter.await.unwrap(), Err(AppError::GameNotFound)));
+        assert_eq!(pool.stats().waiters, 0);
 
-    fn create_client(&self) -> Arc<Client> {
-        Arc::new(self.factory.build_client())
+        drop(busy);
+        assert_eq!(pool.permits.available_permits(), 1);
+        assert!(pool.acquire(never()).await.is_ok());
     }
 }
@@ -1,8 +1,22 @@
 #![allow(dead_code)]
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::*;
+use anyhow::{bail, Context};
 use serde::Deserialize;
 use std::fs;
 use log::error;
+use shared::locale::Language;
+use shared::messages::Difficulty;
+
+use crate::assets::Assets;
+use crate::gpt::{Model, ReasoningEffort, Verbosity};
+
+/// Prefix of the environment variables overriding the config file, e.g.
+/// `GGGAME_WWW__PORT=8080` sets `port` of `[www]`.
+pub const ENV_PREFIX: &str = "GGGAME_";
+/// Environment variable holding the config file path.
+pub const ENV_CONFIG: &str = "GGGAME_CONFIG";
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Config {
@@ -11,6 +25,33 @@ pub struct Config {
     pub gpt: Gpt,
     pub logfile: Option<String>,
     pub dirs: Dirs,
+    #[serde(default)]
+    pub guard: Guard,
+    #[serde(default)]
+    pub answer: AnswerModel,
+    #[serde(default)]
+    pub difficulty: DifficultyLevels,
+    #[serde(default)]
+    pub consistency: Consistency,
+    #[serde(default)]
+    pub facts: Facts,
+    #[serde(default)]
+    pub answer_cache: AnswerCache,
+    #[serde(default)]
+    pub accounting: Accounting,
+    /// Prices per model name, e.g. `[pricing.gpt-5-nano]`.
+    #[serde(default)]
+    pub pricing: HashMap<String, Price>,
+    #[serde(default)]
+    pub admin: Admin,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    #[serde(default)]
+    pub tokens: Tokens,
+
+    /// Keys of the config file not known to the server.
+    #[serde(skip)]
+    pub unused_keys: Vec<String>,
 }
 
 
@@ -47,18 +88,389 @@ impl Dirs {
 #[derive(Deserialize, Debug, Clone)]
 pub struct Www {
     pub port: u16,
+    /// Addresses the server listens on, each with `port`.
+    #[serde(default = "default_bind")]
+    pub bind: Vec<IpAddr>,
+    /// Peers whose `X-Forwarded-For`/`X-Real-IP` headers are believed.
+    #[serde(default = "default_trusted_proxies")]
+    pub trusted_proxies: Vec<IpAddr>,
+    /// TLS is terminated by the server when set.
+    #[serde(default)]
+    pub tls: Option<Tls>,
+    /// How long questions being answered may take once shutdown starts.
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+/// PEM encoded certificate chain and private key.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Tls {
+    pub cert_file: String,
+    pub key_file: String,
+}
+
+fn default_bind() -> Vec<IpAddr> {
+    vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    20
+}
+
+fn default_instructions_file() -> String {
+    "instructions.txt".to_string()
+}
+
+fn default_queue_limit() -> usize {
+    100
+}
+
+fn default_queue_timeout_secs() -> u64 {
+    30
+}
+
+fn default_trusted_proxies() -> Vec<IpAddr> {
+    vec![IpAddr::V4(Ipv4Addr::LOCALHOST), IpAddr::V6(Ipv6Addr::LOCALHOST)]
 }
 
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Gpt {
-    pub key_file: String,
+    /// Model instructions template, in the assets directory; `instructions_cs.txt`
+    /// next to `instructions.txt` is used for Czech games when present.
+    #[serde(default = "default_instructions_file")]
+    pub instructions_file: String,
+    /// Comments of the model may hint at the identity (the `hints` variable of the instructions).
+    #[serde(default)]
+    pub hints: bool,
+    /// Single key file; more keys with their own limits go to `[[gpt.keys]]`.
+    #[serde(default)]
+    pub key_file: Option<String>,
+    #[serde(default)]
+    pub keys: Vec<ApiKey>,
+    /// Named instructions tried side by side; `instructions_file` is the only
+    /// variant when none is configured.
+    #[serde(default)]
+    pub variants: Vec<PromptVariant>,
+    /// Maximum number of concurrent model calls.
     pub max_clients_count: u32,
+    /// Maximum number of calls waiting for a free client.
+    #[serde(default = "default_queue_limit")]
+    pub queue_limit: usize,
+    /// How long a call waits for a free client.
+    #[serde(default = "default_queue_timeout_secs")]
+    pub queue_timeout_secs: u64,
+}
 
-    #[serde(skip)]
-    pub gpt_instructions: String,
-    #[serde(skip)]
-    pub gpt_key: String,
+/// OpenAI API key source; the file may hold several keys, one per line.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiKey {
+    pub name: Option<String>,
+    pub key_file: String,
+    /// Concurrent calls on the key; unlimited when not set.
+    pub max_concurrent: Option<usize>,
+    /// The key is rested once its spend of the day reaches the limit.
+    pub daily_limit_usd: Option<f64>,
+}
+
+fn default_variant_weight() -> u32 {
+    1
+}
+
+/// Instructions variant of a prompt experiment; each new game gets one of the
+/// variants, picked with probability proportional to its weight.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PromptVariant {
+    pub name: String,
+    /// Template in the assets directory, localized like `gpt.instructions_file`.
+    pub instructions_file: String,
+    /// Share of the new games; 0 stops assigning the variant.
+    #[serde(default = "default_variant_weight")]
+    pub weight: u32,
+}
+
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardMode {
+    /// Suspicious questions are only flagged in the records; the model still answers them.
+    Flag,
+    /// Suspicious questions are answered with BEHAVE without calling the model.
+    Reject,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Guard {
+    pub enabled: bool,
+    pub mode: GuardMode,
+    /// Score at which the question is logged as suspicious.
+    pub flag_score: u32,
+    /// Score at which the question is rejected (in `reject` mode).
+    pub reject_score: u32,
+}
+
+impl Default for Guard {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mode: GuardMode::Reject,
+            flag_score: 3,
+            reject_score: 10,
+        }
+    }
+}
+
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsistencyMode {
+    /// Contradicting records are only marked.
+    Flag,
+    /// The new question is asked again; records are marked if the contradiction stays.
+    Reask,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DifficultyLevels {
+    /// Questions of a hard game; the game is lost when they run out.
+    pub hard_question_limit: usize,
+}
+
+impl Default for DifficultyLevels {
+    fn default() -> Self {
+        Self {
+            hard_question_limit: 20,
+        }
+    }
+}
+
+impl DifficultyLevels {
+    pub fn question_limit(&self, difficulty: Difficulty) -> Option<usize> {
+        (difficulty == Difficulty::Hard).then_some(self.hard_question_limit)
+    }
+}
+
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AnswerModel {
+    /// Model answering the questions, e.g. "gpt-5-nano".
+    pub model: String,
+    /// Model asked again when the first one fails or its verdict can't be parsed.
+    pub fallback_model: Option<String>,
+    /// "low", "medium" or "high".
+    pub verbosity: String,
+    /// "minimal", "low", "medium" or "high"; the API default when not set.
+    pub reasoning_effort: Option<String>,
+    pub max_output_tokens: Option<i32>,
+    pub temperature: Option<f32>,
+    /// Overrides by profile name: a difficulty level or the profile a game template asks for.
+    pub profiles: HashMap<String, ModelProfile>,
+}
+
+impl Default for AnswerModel {
+    fn default() -> Self {
+        Self {
+            model: "gpt-5-nano".to_string(),
+            fallback_model: None,
+            verbosity: "medium".to_string(),
+            reasoning_effort: None,
+            max_output_tokens: None,
+            temperature: None,
+            profiles: HashMap::new(),
+        }
+    }
+}
+
+/// Settings of `[answer]` changed by a profile; the others are inherited.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ModelProfile {
+    pub model: Option<String>,
+    pub fallback_model: Option<String>,
+    pub verbosity: Option<String>,
+    pub reasoning_effort: Option<String>,
+    pub max_output_tokens: Option<i32>,
+    pub temperature: Option<f32>,
+}
+
+impl AnswerModel {
+    /// The settings with the overrides of the profile applied.
+    pub fn with_profile(&self, profile: Option<&str>) -> AnswerModel {
+        let mut settings = self.clone();
+        settings.profiles.clear();
+        let Some(p) = profile.and_then(|name| self.profiles.get(name)) else {
+            return settings;
+        };
+        if let Some(model) = &p.model {
+            settings.model = model.clone();
+        }
+        if let Some(verbosity) = &p.verbosity {
+            settings.verbosity = verbosity.clone();
+        }
+        settings.fallback_model = p.fallback_model.clone().or(settings.fallback_model);
+        settings.reasoning_effort = p.reasoning_effort.clone().or(settings.reasoning_effort);
+        settings.max_output_tokens = p.max_output_tokens.or(settings.max_output_tokens);
+        settings.temperature = p.temperature.or(settings.temperature);
+        settings
+    }
+
+    pub fn has_profile(&self, name: &str) -> bool {
+        self.profiles.contains_key(name)
+    }
+}
+
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Consistency {
+    pub enabled: bool,
+    pub mode: ConsistencyMode,
+    /// Model used for the verification pass, e.g. "gpt-5-mini".
+    pub model: String,
+    /// Maximum number of verification passes per game.
+    pub max_checks_per_game: u32,
+    pub max_output_tokens: Option<i32>,
+}
+
+impl Default for Consistency {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: ConsistencyMode::Flag,
+            model: "gpt-5-mini".to_string(),
+            max_checks_per_game: 30,
+            max_output_tokens: None,
+        }
+    }
+}
+
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Facts {
+    pub enabled: bool,
+    /// Model generating the fact sheets of identities.
+    pub model: String,
+    pub max_output_tokens: Option<i32>,
+}
+
+impl Default for Facts {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: "gpt-5-mini".to_string(),
+            max_output_tokens: None,
+        }
+    }
+}
+
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AnswerCache {
+    pub enabled: bool,
+    pub ttl_secs: u64,
+    pub max_entries: usize,
+    /// The cache is loaded from and periodically saved to this file.
+    pub persist_file: Option<String>,
+    pub persist_interval_secs: u64,
+}
+
+impl Default for AnswerCache {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 7 * 24 * 3600,
+            max_entries: 10000,
+            persist_file: None,
+            persist_interval_secs: 300,
+        }
+    }
+}
+
+
+/// USD per million tokens.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Price {
+    pub input_per_mtok: f64,
+    pub cached_input_per_mtok: Option<f64>,
+    pub output_per_mtok: f64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Accounting {
+    /// New questions are refused once the spend of the day reaches the limit.
+    pub daily_limit_usd: Option<f64>,
+    pub monthly_limit_usd: Option<f64>,
+    /// Number of recent calls kept for the admin endpoint.
+    pub recent_calls: usize,
+    /// Number of games with their own spend kept; the least recently charged are dropped.
+    pub max_games: usize,
+    pub persist_file: Option<String>,
+    pub persist_interval_secs: u64,
+}
+
+impl Default for Accounting {
+    fn default() -> Self {
+        Self {
+            daily_limit_usd: None,
+            monthly_limit_usd: None,
+            recent_calls: 100,
+            max_games: 1000,
+            persist_file: None,
+            persist_interval_secs: 60,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Admin {
+    /// Bearer token of the admin endpoints; they are disabled when unset.
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Tokens {
+    /// Key signing the tokens; a random one per start when unset, which
+    /// invalidates the tokens handed out before a restart.
+    pub secret: Option<String>,
+    /// Lifetime of the game and spectator tokens, and of the games; 0 never expires.
+    pub game_ttl_secs: u64,
+}
+
+/// Token bucket holding up to `burst` tokens, refilled by `per_minute`.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct Bucket {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RateLimit {
+    pub enabled: bool,
+    /// Games, templates and player sessions created by one client IP.
+    pub new_game: Bucket,
+    /// Questions asked within one game.
+    pub question: Bucket,
+    /// Model calls of the whole server.
+    pub model: Bucket,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            new_game: Bucket { burst: 10, per_minute: 5 },
+            question: Bucket { burst: 5, per_minute: 20 },
+            model: Bucket { burst: 50, per_minute: 300 },
+        }
+    }
 }
 
 
@@ -100,6 +512,66 @@ impl Config {
         self.dirs.get_path(DirType::Assets).join(filename)
     }
 
+    /// Identities of an easy or hard game, `identities_<level>_<lang>.txt`; the
+    /// file may be missing, the normal catalog is used then.
+    pub fn get_difficulty_identities_file(&self, lang: &shared::locale::Language, difficulty: Difficulty) -> PathBuf {
+        let filename = format!("identities_{}_{}.txt", difficulty.to_code(), lang.to_code());
+        self.dirs.get_path(DirType::Assets).join(filename)
+    }
+
+    /// The instructions template `file` of the assets, or its `_<lang>` version when present.
+    pub fn get_instructions_file(&self, file: &str, lang: &shared::locale::Language) -> PathBuf {
+        let default = self.dirs.get_path(DirType::Assets).join(file);
+        let file = Path::new(file);
+        let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let localized = match file.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{}_{}.{}", stem, lang.to_code(), ext),
+            None => format!("{}_{}", stem, lang.to_code()),
+        };
+        let localized = default.with_file_name(localized);
+        if localized.is_file() { localized } else { default }
+    }
+
+    /// Files included by the instructions templates.
+    pub fn get_include_file(&self, name: &str) -> PathBuf {
+        self.dirs.get_path(DirType::Assets).join(name)
+    }
+
+    /// Optional overrides of the built-in translations.
+    pub fn get_translations_file(&self, lang: &shared::locale::Language) -> PathBuf {
+        let filename = match lang {
+            shared::locale::Language::English => "translations_en.toml",
+            shared::locale::Language::Czech => "translations_cs.toml",
+        };
+        self.dirs.get_path(DirType::Assets).join(filename)
+    }
+
+    pub fn get_guard_patterns_file(&self, lang: &shared::locale::Language) -> PathBuf {
+        let filename = match lang {
+            shared::locale::Language::English => "guard_patterns_en.txt",
+            shared::locale::Language::Czech => "guard_patterns_cs.txt",
+        };
+        self.dirs.get_path(DirType::Assets).join(filename)
+    }
+
+    pub fn get_facts_file(&self, lang: &shared::locale::Language) -> PathBuf {
+        let filename = match lang {
+            shared::locale::Language::English => "facts_en.json",
+            shared::locale::Language::Czech => "facts_cs.json",
+        };
+        self.dirs.get_path(DirType::Assets).join(filename)
+    }
+
+    pub fn get_answer_cache_file(&self) -> Option<PathBuf> {
+        let file = self.answer_cache.persist_file.as_ref()?;
+        Some(self.dirs.get_path(DirType::Root).join(file))
+    }
+
+    pub fn get_accounting_file(&self) -> Option<PathBuf> {
+        let file = self.accounting.persist_file.as_ref()?;
+        Some(self.dirs.get_path(DirType::Root).join(file))
+    }
+
     fn get_path(&self, component: &str, file: &str) -> PathBuf {
         PathBuilder::new()
             .join(component)
@@ -107,23 +579,301 @@ impl Config {
             .build()
     }
 
+    /// Key sources of `[gpt]`, the plain `key_file` first.
+    pub fn get_api_keys(&self) -> Vec<ApiKey> {
+        let legacy = self.gpt.key_file.iter().map(|key_file| ApiKey {
+            name: Some("default".to_string()),
+            key_file: key_file.clone(),
+            max_concurrent: None,
+            daily_limit_usd: None,
+        });
+        legacy.chain(self.gpt.keys.iter().cloned()).collect()
+    }
+
+    /// Prompt variants of `[[gpt.variants]]`; just `instructions_file`, named
+    /// "default", without them.
+    pub fn get_prompt_variants(&self) -> Vec<PromptVariant> {
+        if !self.gpt.variants.is_empty() {
+            return self.gpt.variants.clone();
+        }
+        vec![PromptVariant {
+            name: "default".to_string(),
+            instructions_file: self.gpt.instructions_file.clone(),
+            weight: default_variant_weight(),
+        }]
+    }
+
+    pub fn get_listen_addrs(&self) -> Vec<SocketAddr> {
+        self.www.bind.iter().map(|ip| SocketAddr::new(*ip, self.www.port)).collect()
+    }
+
+    /// Certificate and key file of `[www.tls]`.
+    pub fn get_tls_files(&self) -> Option<(PathBuf, PathBuf)> {
+        let tls = self.www.tls.as_ref()?;
+        let root = self.dirs.get_path(DirType::Root);
+        Some((root.join(&tls.cert_file), root.join(&tls.key_file)))
+    }
+
+    pub fn get_key_path(&self, key: &ApiKey) -> PathBuf {
+        self.dirs.get_path(DirType::Root).join(&key.key_file)
+    }
+
     fn read_path(&self, dir: DirType, file: &str) -> Result<String, anyhow::Error> {
         let f = self.dirs.get_path(dir).join(file);
         match fs::read_to_string(f.clone()) {
-            Ok(res) => return Ok(res),
+            Ok(res) => Ok(res),
             Err(err) => {
                 error!("file {} not found, err={}", f.to_str().unwrap(), err);
-                return Err(err.into());
+                Err(err.into())
             }
         }
     }
 
+    /// Parses the config file with the `GGGAME_*` overrides applied; the
+    /// files it refers to are not checked.
+    pub fn load(file_name: &str) -> Result<Config, anyhow::Error> {
+        let contents = fs::read_to_string(file_name)
+            .with_context(|| format!("config file {} not readable", file_name))?;
+        let mut table = toml::from_str::<toml::Table>(&contents)
+            .with_context(|| format!("config file {} is not valid TOML", file_name))?;
+        apply_env_overrides(&mut table, std::env::vars())?;
+
+        let mut unused_keys = Vec::new();
+        let mut c: Config = serde_ignored::deserialize(toml::Value::Table(table), |path| {
+            unused_keys.push(path.to_string());
+        }).with_context(|| format!("config file {} is not valid", file_name))?;
+        c.unused_keys = unused_keys;
+        Ok(c)
+    }
+
+    /// Every problem of the configuration, so that all of them can be fixed at once.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for key in &self.unused_keys {
+            problems.push(format!("unknown key `{}`", key));
+        }
+
+        let dirs = [
+            ("www", DirType::Www),
+            ("dist", DirType::Dist),
+            ("assets", DirType::Assets),
+        ];
+        for (name, dir) in dirs {
+            let path = self.dirs.get_path(dir);
+            if !path.is_dir() {
+                problems.push(format!("{} directory {} not found", name, path.display()));
+            }
+        }
+
+        let mut required = vec![self.dirs.get_path(DirType::Dist).join("index.html")];
+        let variants = self.get_prompt_variants();
+        for lang in [Language::English, Language::Czech] {
+            for variant in &variants {
+                required.push(self.get_instructions_file(&variant.instructions_file, &lang));
+            }
+            required.push(self.get_identities_file(&lang));
+        }
+        if let Some((cert, key)) = self.get_tls_files() {
+            required.extend([cert, key]);
+        }
+        let found = problems.len();
+        for (i, path) in required.iter().enumerate() {
+            if !path.is_file() && !required[..i].contains(path) {
+                problems.push(format!("file {} not found", path.display()));
+            }
+        }
+        if problems.len() == found {
+            if let Err(err) = Assets::load(self) {
+                problems.push(format!("{:#}", err));
+            }
+        }
+
+        let keys = self.get_api_keys();
+        if keys.is_empty() {
+            problems.push("no OpenAI API key configured; set gpt.key_file or [[gpt.keys]]".to_string());
+        }
+        for key in &keys {
+            let path = self.get_key_path(key);
+            match fs::read_to_string(&path) {
+                Ok(content) if content.lines().any(|l| !l.trim().is_empty() && !l.trim().starts_with('#')) => (),
+                Ok(_) => problems.push(format!("key file {} holds no key", path.display())),
+                Err(err) => problems.push(format!("key file {} not readable: {}", path.display(), err)),
+            }
+        }
+
+        for (i, variant) in variants.iter().enumerate() {
+            if variant.name.trim().is_empty() {
+                problems.push(format!("gpt.variants[{}] has no name", i));
+            } else if variants[..i].iter().any(|v| v.name == variant.name) {
+                problems.push(format!("prompt variant `{}` is defined twice", variant.name));
+            }
+        }
+        if variants.iter().all(|v| v.weight == 0) {
+            problems.push("every prompt variant has weight 0".to_string());
+        }
+
+        if self.www.bind.is_empty() {
+            problems.push("www.bind holds no address".to_string());
+        }
+        if self.difficulty.hard_question_limit == 0 {
+            problems.push("difficulty.hard_question_limit must be at least 1".to_string());
+        }
+        if self.tokens.secret.as_ref().is_some_and(|s| s.len() < 16) {
+            problems.push("tokens.secret must be at least 16 characters long".to_string());
+        }
+        if self.gpt.max_clients_count == 0 {
+            problems.push("gpt.max_clients_count must be at least 1".to_string());
+        }
+        let mut models = vec![
+            ("answer.model".to_string(), Some(&self.answer.model)),
+            ("answer.fallback_model".to_string(), self.answer.fallback_model.as_ref()),
+            ("consistency.model".to_string(), Some(&self.consistency.model)),
+            ("facts.model".to_string(), Some(&self.facts.model)),
+        ];
+        let mut verbosities = vec![("answer.verbosity".to_string(), Some(&self.answer.verbosity))];
+        let mut efforts = vec![("answer.reasoning_effort".to_string(), self.answer.reasoning_effort.as_ref())];
+        for (name, profile) in &self.answer.profiles {
+            models.push((format!("answer.profiles.{}.model", name), profile.model.as_ref()));
+            models.push((format!("answer.profiles.{}.fallback_model", name), profile.fallback_model.as_ref()));
+            verbosities.push((format!("answer.profiles.{}.verbosity", name), profile.verbosity.as_ref()));
+            efforts.push((format!("answer.profiles.{}.reasoning_effort", name), profile.reasoning_effort.as_ref()));
+        }
+        for (name, model) in models {
+            if let Some(model) = model.filter(|m| m.parse::<Model>().is_err()) {
+                problems.push(format!("{} `{}` is not a known model", name, model));
+            }
+        }
+        for (name, verbosity) in verbosities {
+            if let Some(verbosity) = verbosity.filter(|v| v.parse::<Verbosity>().is_err()) {
+                problems.push(format!("{} `{}` is not one of low, medium, high", name, verbosity));
+            }
+        }
+        for (name, effort) in efforts {
+            if let Some(effort) = effort.filter(|e| e.parse::<ReasoningEffort>().is_err()) {
+                problems.push(format!("{} `{}` is not one of minimal, low, medium, high", name, effort));
+            }
+        }
+        if self.rate_limit.enabled {
+            let buckets = [
+                ("new_game", self.rate_limit.new_game),
+                ("question", self.rate_limit.question),
+                ("model", self.rate_limit.model),
+            ];
+            for (name, bucket) in buckets {
+                if bucket.burst == 0 || bucket.per_minute == 0 {
+                    problems.push(format!("rate_limit.{} would refuse every request", name));
+                }
+            }
+        }
+
+        problems
+    }
+
     pub fn read(file_name: &str) -> Result<Config, anyhow::Error> {
-        let contents = fs::read_to_string(file_name)?;
-        let mut c = toml::from_str::<Config>(&contents)?;
+        let c = Config::load(file_name)?;
 
-        c.gpt.gpt_instructions = c.read_path(DirType::Assets, "instructions.txt")?;
-        c.gpt.gpt_key = c.read_path(DirType::Root, &c.gpt.key_file)?.trim().to_string();
+        let problems = c.problems();
+        if !problems.is_empty() {
+            bail!("invalid config {}:\n  {}", file_name, problems.join("\n  "));
+        }
         Ok(c)
     }
 }
+
+/// Key of the table named by a variable. Variable names can't hold hyphens,
+/// so `gpt_5_mini` names an existing `gpt-5-mini` key; new keys are taken as
+/// they are spelled.
+fn env_key(table: &toml::Table, key: &str) -> String {
+    if table.contains_key(key) {
+        return key.to_string();
+    }
+    table.keys()
+        .find(|k| k.replace('-', "_") == key)
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Applies `GGGAME_SECTION__KEY=value` variables; the value is read as TOML
+/// (`8080`, `true`, `["::1"]`) and taken as a plain string otherwise.
+fn apply_env_overrides(
+    table: &mut toml::Table,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<(), anyhow::Error> {
+    for (name, raw) in vars {
+        let Some(path) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if name == ENV_CONFIG || path.is_empty() {
+            continue;
+        }
+        let value = toml::from_str::<toml::Table>(&format!("v = {}", raw))
+            .ok()
+            .and_then(|mut t| t.remove("v"))
+            .unwrap_or_else(|| toml::Value::String(raw.clone()));
+
+        let keys = path.split("__").map(str::to_lowercase).collect::<Vec<_>>();
+        let (last, sections) = keys.split_last().unwrap();
+        let mut current = &mut *table;
+        for section in sections {
+            let entry = current.entry(env_key(current, section))
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            current = match entry {
+                toml::Value::Table(t) => t,
+                _ => bail!("{} overrides `{}`, which is not a section", name, section),
+            };
+        }
+        current.insert(env_key(current, last), value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(toml: &str, vars: &[(&str, &str)]) -> Result<toml::Table, anyhow::Error> {
+        let mut table = toml::from_str::<toml::Table>(toml).unwrap();
+        let vars = vars.iter().map(|(k, v)| (k.to_string(), v.to_string()));
+        apply_env_overrides(&mut table, vars)?;
+        Ok(table)
+    }
+
+    #[test]
+    fn values_are_read_as_toml() {
+        let table = apply("[www]\nport = 3000\n", &[
+            ("GGGAME_WWW__PORT", "8080"),
+            ("GGGAME_WWW__TRUSTED_PROXIES", r#"["::1"]"#),
+            ("GGGAME_GPT__MODEL", "gpt-5"),
+        ]).unwrap();
+        assert_eq!(table["www"]["port"].as_integer(), Some(8080));
+        assert_eq!(table["www"]["trusted_proxies"][0].as_str(), Some("::1"));
+        assert_eq!(table["gpt"]["model"].as_str(), Some("gpt-5"));
+    }
+
+    #[test]
+    fn other_variables_are_ignored() {
+        let table = apply("debug = true\n", &[
+            ("HOME", "/root"),
+            (ENV_CONFIG, "/etc/gggame.toml"),
+            ("GGGAME_", "x"),
+        ]).unwrap();
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn underscores_match_hyphenated_keys() {
+        let table = apply("[pricing.gpt-5-mini]\ninput_per_mtok = 0.25\n", &[
+            ("GGGAME_PRICING__GPT_5_MINI__INPUT_PER_MTOK", "0.5"),
+            ("GGGAME_PRICING__GPT_5_NANO__INPUT_PER_MTOK", "0.1"),
+        ]).unwrap();
+        assert_eq!(table["pricing"]["gpt-5-mini"]["input_per_mtok"].as_float(), Some(0.5));
+        assert_eq!(table["pricing"]["gpt_5_nano"]["input_per_mtok"].as_float(), Some(0.1));
+        assert!(!table["pricing"].as_table().unwrap().contains_key("gpt_5_mini"));
+    }
+
+    #[test]
+    fn value_is_not_a_section() {
+        assert!(apply("debug = true\n", &[("GGGAME_DEBUG__LEVEL", "1")]).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use shared::messages::{AdminVariantStats, Verdict};
+
+use crate::assets::Assets;
+
+#[derive(Debug, Default, Clone)]
+struct Counters {
+    games: u64,
+    answers: u64,
+    solves: u64,
+    give_ups: u64,
+    unable: u64,
+    behave: u64,
+    /// Sum of the questions asked in the solved games.
+    questions_to_solve: u64,
+    cost_usd: f64,
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 / whole as f64 }
+}
+
+/// Outcomes of the games per prompt variant, kept in memory since the start.
+#[derive(Default)]
+pub struct Experiments {
+    variants: Mutex<HashMap<String, Counters>>,
+}
+
+impl Experiments {
+    fn update(&self, variant: &str, f: impl FnOnce(&mut Counters)) {
+        f(self.variants.lock().unwrap().entry(variant.to_string()).or_default());
+    }
+
+    pub fn game_started(&self, variant: &str) {
+        self.update(variant, |c| c.games += 1);
+    }
+
+    /// Counts the answer to the `question`-th question of the game; a FINAL
+    /// answer of a give-up isn't a solve.
+    pub fn answered(&self, variant: &str, verdict: &Option<Verdict>, question: usize, gave_up: bool) {
+        self.update(variant, |c| {
+            c.answers += 1;
+            match verdict {
+                Some(Verdict::Final) if gave_up => c.give_ups += 1,
+                Some(Verdict::Final) => {
+                    c.solves += 1;
+                    c.questions_to_solve += question as u64;
+                }
+                Some(Verdict::Unable) => c.unable += 1,
+                Some(Verdict::Behave) => c.behave += 1,
+                _ => (),
+            }
+        });
+    }
+
+    pub fn add_cost(&self, variant: &str, cost_usd: f64) {
+        self.update(variant, |c| c.cost_usd += cost_usd);
+    }
+
+    /// Statistics of the variants of the current assets first, then of those
+    /// which were dropped by a reload.
+    pub fn report(&self, assets: &Assets) -> Vec<AdminVariantStats> {
+        let variants = self.variants.lock().unwrap();
+        let mut names = assets.variants().iter()
+            .map(|v| (v.name.clone(), Some(v.weight)))
+            .collect::<Vec<_>>();
+        let mut retired = variants.keys()
+            .filter(|name| !names.iter().any(|(n, _)| n == *name))
+            .map(|name| (name.clone(), None))
+            .collect::<Vec<_>>();
+        retired.sort();
+        names.extend(retired);
+
+        names.into_iter()
+            .map(|(name, weight)| {
+                let c = variants.get(&name).cloned().unwrap_or_default();
+                AdminVariantStats {
+                    weight,
+                    games: c.games,
+                    answers: c.answers,
+                    solves: c.solves,
+                    give_ups: c.give_ups,
+                    solve_rate: ratio(c.solves, c.games),
+                    unable_rate: ratio(c.unable, c.answers),
+                    behave_rate: ratio(c.behave, c.answers),
+                    avg_questions_to_solve: (c.solves > 0).then(|| ratio(c.questions_to_solve, c.solves)),
+                    cost_usd: c.cost_usd,
+                    cost_per_game_usd: if c.games == 0 { 0.0 } else { c.cost_usd / c.games as f64 },
+                    name,
+                }
+            })
+            .collect()
+    }
+}
+
+static EXPERIMENTS: OnceLock<Experiments> = OnceLock::new();
+
+pub fn experiments() -> &'static Experiments {
+    EXPERIMENTS.get_or_init(Experiments::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::test_assets;
+
+    fn stats<'a>(report: &'a [AdminVariantStats], name: &str) -> &'a AdminVariantStats {
+        report.iter().find(|s| s.name == name).unwrap()
+    }
+
+    #[test]
+    fn outcomes_are_counted_per_variant() {
+        let experiments = Experiments::default();
+        for _ in 0..4 {
+            experiments.game_started("a");
+        }
+        experiments.answered("a", &Some(Verdict::Yes), 1, false);
+        experiments.answered("a", &Some(Verdict::Unable), 2, false);
+        experiments.answered("a", &Some(Verdict::Final), 3, false);
+        experiments.answered("a", &Some(Verdict::Behave), 1, false);
+        experiments.answered("a", &Some(Verdict::Final), 5, false);
+        experiments.answered("a", &Some(Verdict::Final), 7, true);
+        experiments.answered("a", &None, 8, false);
+        experiments.add_cost("a", 0.5);
+        experiments.add_cost("a", 0.3);
+        experiments.game_started("b");
+
+        let report = experiments.report(&test_assets(&[("a", 2), ("b", 1)]));
+        let a = stats(&report, "a");
+        assert_eq!((a.games, a.answers, a.solves, a.give_ups), (4, 7, 2, 1));
+        assert_eq!(a.weight, Some(2));
+        assert_eq!(a.solve_rate, 0.5);
+        assert_eq!(a.unable_rate, 1.0 / 7.0);
+        assert_eq!(a.behave_rate, 1.0 / 7.0);
+        assert_eq!(a.avg_questions_to_solve, Some(4.0));
+        assert!((a.cost_usd - 0.8).abs() < 1e-9);
+        assert!((a.cost_per_game_usd - 0.2).abs() < 1e-9);
+
+        let b = stats(&report, "b");
+        assert_eq!((b.games, b.answers, b.solves), (1, 0, 0));
+        assert_eq!((b.unable_rate, b.avg_questions_to_solve), (0.0, None));
+    }
+
+    #[test]
+    fn retired_variants_are_reported_last() {
+        let experiments = Experiments::default();
+        experiments.game_started("old-z");
+        experiments.game_started("old-a");
+        experiments.game_started("b");
+
+        let report = experiments.report(&test_assets(&[("b", 1), ("unused", 0)]));
+        let names = report.iter().map(|s| s.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["b", "unused", "old-a", "old-z"]);
+        assert_eq!(stats(&report, "unused").games, 0);
+        assert_eq!(stats(&report, "unused").cost_per_game_usd, 0.0);
+        assert_eq!(stats(&report, "old-a").weight, None);
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use dashmap::{DashMap, Entry};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use shared::locale::Language;
+
+use crate::config::{Config, Facts};
+use crate::gpt::{GptClient, Model, QuestionParams};
+use crate::usage::{Purpose, UsageTag};
+
+const FACTS_INSTRUCTIONS: &str = "You prepare a fact sheet for a game of Guess Who.
+The hidden identity is: [{target}]
+
+Fill in the key attributes of the identity as they would be used to answer
+yes/no questions. Use \"UNKNOWN\" when the attribute doesn't apply or is
+disputed. Keep the text fields short (a few words).";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Fact {
+    Yes,
+    No,
+    Unknown,
+}
+
+impl Fact {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Fact::Yes => "YES",
+            Fact::No => "NO",
+            Fact::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// Compact set of yes/no attributes of an identity. It's added to the
+/// instructions of every question, so the answers don't drift.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FactSheet {
+    pub living: Fact,
+    pub ever_lived: Fact,
+    pub person: Fact,
+    pub animal: Fact,
+    pub human_made: Fact,
+    pub fictional: Fact,
+    pub size: String,
+    pub era: String,
+    pub location: String,
+}
+
+impl FactSheet {
+    pub fn to_prompt(&self) -> String {
+        format!(
+            "- alive now: {}\n- ever lived: {}\n- a person: {}\n- an animal: {}\n\
+             - made by humans: {}\n- fictional: {}\n- size: {}\n- era: {}\n- location: {}",
+            self.living.as_str(), self.ever_lived.as_str(), self.person.as_str(),
+            self.animal.as_str(), self.human_made.as_str(), self.fictional.as_str(),
+            self.size, self.era, self.location,
+        )
+    }
+}
+
+fn fact_schema() -> serde_json::Value {
+    let fact = json!({ "type": "string", "enum": ["YES", "NO", "UNKNOWN"] });
+    let text = json!({ "type": "string" });
+    json!({
+        "type": "object",
+        "properties": {
+            "living": fact,
+            "ever_lived": fact,
+            "person": fact,
+            "animal": fact,
+            "human_made": fact,
+            "fictional": fact,
+            "size": text,
+            "era": text,
+            "location": text
+        },
+        "required": ["living", "ever_lived", "person", "animal", "human_made",
+                     "fictional", "size", "era", "location"],
+        "additionalProperties": false
+    })
+}
+
+/// Fact sheet of the model reply, JSON of `fact_schema()`.
+fn parse_reply(text: &str) -> Result<FactSheet> {
+    Ok(serde_json::from_str(text)?)
+}
+
+/// Precomputed fact sheets, a JSON object mapping identity to its fact sheet.
+fn read_precomputed(path: PathBuf) -> Result<HashMap<String, FactSheet>> {
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow::anyhow!("Failed to read fact sheets file {:?}: {}", path, e))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Cache of fact sheets keyed by language and identity. `None` marks a sheet
+/// which is being generated.
+pub struct FactSheetStore {
+    config: Facts,
+    model: Model,
+    sheets: DashMap<(Language, String), Option<FactSheet>>,
+}
+
+impl FactSheetStore {
+    pub fn new(config: &Config) -> Self {
+        let model = config.facts.model.parse::<Model>().unwrap_or_else(|_| {
+            warn!("unknown fact sheet model \"{}\", using {}", config.facts.model, Model::Gpt5Mini);
+            Model::Gpt5Mini
+        });
+
+        let sheets = DashMap::new();
+        for lang in [Language::English, Language::Czech] {
+            let path = config.get_facts_file(&lang);
+            if !path.exists() {
+                continue;
+            }
+            match read_precomputed(path) {
+                Ok(precomputed) => {
+                    info!("loaded {} precomputed fact sheets for {}", precomputed.len(), lang.to_code());
+                    for (identity, sheet) in precomputed {
+                        sheets.insert((lang.clone(), identity), Some(sheet));
+                    }
+                }
+                Err(err) => warn!("{}", err),
+            }
+        }
+
+        Self {
+            config: config.facts.clone(),
+            model,
+            sheets,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn get(&self, lang: &Language, identity: &str) -> Option<FactSheet> {
+        if !self.config.enabled {
+            return None;
+        }
+        self.sheets.get(&(lang.clone(), identity.to_string()))?.clone()
+    }
+
+    /// Marks the sheet as being generated. Returns false if it's cached or
+    /// somebody else is already generating it.
+    pub fn begin_generate(&self, lang: &Language, identity: &str) -> bool {
+        match self.sheets.entry((lang.clone(), identity.to_string())) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(None);
+                true
+            }
+        }
+    }
+
+    /// Drops the mark of `begin_generate()` when the sheet won't be generated.
+    pub fn cancel_generate(&self, lang: &Language, identity: &str) {
+        self.sheets.remove_if(&(lang.clone(), identity.to_string()), |_, sheet| sheet.is_none());
+    }
+
+    fn build_params(&self, identity: &str) -> QuestionParams {
+        let mut params = QuestionParams::default();
+        params.set_model(self.model);
+        params.set_max_output_tokens(self.config.max_output_tokens);
+        params.set_instructions(FACTS_INSTRUCTIONS.replace("{target}", identity));
+        params.set_json_schema("fact_sheet", fact_schema());
+        params.set_usage_tag(UsageTag::new(Purpose::FactSheet));
+        params
+    }
+
+    async fn ask(&self, client: &GptClient, identity: &str) -> Result<FactSheet> {
+        let input = format!("identity: [{}]", identity.replace(['[', ']'], "/"));
+        let reply = client.ask(&input, &self.build_params(identity)).await?;
+        parse_reply(&reply.to_string().unwrap_or_default())
+    }
+
+    /// Generates the sheet; must be preceded by a successful `begin_generate()`.
+    pub async fn generate(&self, client: &GptClient, lang: &Language, identity: &str) {
+        let key = (lang.clone(), identity.to_string());
+        match self.ask(client, identity).await {
+            Ok(sheet) => {
+                info!("fact sheet ready for [{}]", identity);
+                self.sheets.insert(key, Some(sheet));
+            }
+            Err(err) => {
+                warn!("fact sheet generation failed for [{}]: {}", identity, err);
+                self.sheets.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPLY: &str = r#"{"living": "NO", "ever_lived": "YES", "person": "YES", "animal": "NO",
+        "human_made": "NO", "fictional": "NO", "size": "human", "era": "19th century",
+        "location": "Europe"}"#;
+
+    #[test]
+    fn sheet_is_listed_in_the_prompt() {
+        let sheet = parse_reply(REPLY).unwrap();
+        assert_eq!(sheet.to_prompt(), "- alive now: NO\n- ever lived: YES\n- a person: YES\n\
+            - an animal: NO\n- made by humans: NO\n- fictional: NO\n- size: human\n\
+            - era: 19th century\n- location: Europe");
+    }
+
+    #[test]
+    fn reply_is_parsed() {
+        let sheet = parse_reply(REPLY).unwrap();
+        assert_eq!((sheet.living, sheet.ever_lived, sheet.human_made), (Fact::No, Fact::Yes, Fact::No));
+        assert_eq!(sheet.era, "19th century");
+
+        let unknown = parse_reply(&REPLY.replace(r#""fictional": "NO""#, r#""fictional": "UNKNOWN""#)).unwrap();
+        assert_eq!(unknown.fictional, Fact::Unknown);
+    }
+
+    #[test]
+    fn broken_replies_are_refused() {
+        assert!(parse_reply("").is_err());
+        assert!(parse_reply("YES").is_err());
+        assert!(parse_reply(&REPLY.replace(r#""living": "NO""#, r#""living": "MAYBE""#)).is_err());
+        assert!(parse_reply(&REPLY.replace(r#""living": "NO""#, r#""living": "no""#)).is_err());
+        assert!(parse_reply(&REPLY.replace(r#""size": "human","#, "")).is_err());
+    }
+}
@@ -1,11 +1,12 @@
+use std::future::Future;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
 use dashmap::DashMap;
 use dashmap::mapref::one::RefMut;
-use serde_json;
-use tokio::sync::Notify;
+use ::time::OffsetDateTime;
+use tokio::sync::{watch, Notify};
 use tokio::time;
 use tracing::info;
 
@@ -14,6 +15,10 @@ use shared::messages::*;
 use shared::token::*;
 
 use crate::app_error::AppError;
+use crate::assets::{self, Assets};
+use crate::consistency::Conflict;
+use crate::experiments::experiments;
+use crate::metrics::metrics;
 use crate::token_gen::TokenGen;
 
 pub fn sanitize_question(question: &str) -> Option<String> {
@@ -23,9 +28,47 @@ pub fn sanitize_question(question: &str) -> Option<String> {
     Some(question.to_string())
 }
 
-#[derive(Default)]
 struct StateHelper {
     notifier: Arc<Notify>,
+    /// Wakes up the spectators on every change of the game.
+    spectators: Arc<Notify>,
+    /// Read-only token of the game, created on the first request.
+    spectator: Option<Token>,
+    consistency_checks: u32,
+    template: Option<Token>,
+    /// Unix timestamp of the game creation.
+    created: i64,
+    /// Dropped with the helper, which wakes up everybody watching the game.
+    alive: watch::Sender<()>,
+    /// Assets the game started with.
+    assets: Arc<Assets>,
+    /// Profile of the model settings, from the template.
+    model_profile: Option<String>,
+    /// The player gave up rather than guessed the identity.
+    gave_up: bool,
+    /// The prompt guard flagged the pending question.
+    guard_flagged: bool,
+    /// Player session the game belongs to.
+    session: Option<Token>,
+}
+
+impl StateHelper {
+    fn new(assets: Arc<Assets>) -> Self {
+        Self {
+            assets,
+            model_profile: None,
+            gave_up: false,
+            guard_flagged: false,
+            session: None,
+            notifier: Arc::new(Notify::new()),
+            spectators: Arc::new(Notify::new()),
+            spectator: None,
+            consistency_checks: 0,
+            template: None,
+            created: OffsetDateTime::now_utc().unix_timestamp(),
+            alive: watch::channel(()).0,
+        }
+    }
 }
 
 
@@ -35,6 +78,10 @@ pub struct GameManager {
     game_states: Arc<DashMap<Token, GameState>>,
     helpers: Arc<DashMap<Token, StateHelper>>,
     custom_games: Arc<DashMap<Token, GameTemplate>>,
+    /// Spectator token -> game token.
+    spectator_tokens: Arc<DashMap<Token, Token>>,
+    /// Player session -> its games, the oldest first.
+    sessions: Arc<DashMap<Token, Vec<Token>>>,
 }
 
 impl GameManager {
@@ -43,6 +90,8 @@ impl GameManager {
             game_states: Arc::new(DashMap::new()),
             helpers: Arc::new(DashMap::new()),
             custom_games: Arc::new(DashMap::new()),
+            spectator_tokens: Arc::new(DashMap::new()),
+            sessions: Arc::new(DashMap::new()),
         }
     }
 
@@ -63,21 +112,81 @@ impl GameManager {
 
     pub async fn wait_for_answer(&self, token: &Token, timeout: Duration) -> Result<(), AppError> {
         info!("Waiting for answer BEGIN");
-        let result = time::timeout(timeout, self.get_notifier(token)?.notified()).await
+        time::timeout(timeout, self.get_notifier(token)?.notified()).await
             .map_err(|_| AppError::Timeout)?;
         info!("Waiting for answer END");
-        Ok(result)
+        Ok(())
+    }
+
+    /// Read-only token of the game; the same one for all the spectators.
+    pub fn spectator_token(&self, token: &Token) -> Result<Token, AppError> {
+        let mut helper = self.helpers.get_mut(token).ok_or(AppError::GameNotFound)?;
+        if let Some(spectator) = helper.spectator {
+            return Ok(spectator);
+        }
+        let spectator = Token::new(TokenType::Spectator);
+        helper.spectator = Some(spectator);
+        self.spectator_tokens.insert(spectator, *token);
+        info!("spectator token {} for game {}", spectator, token);
+        Ok(spectator)
+    }
+
+    /// Game watched with the spectator token.
+    pub fn spectated_game(&self, spectator: &Token) -> Result<Token, AppError> {
+        self.spectator_tokens.get(spectator).map(|game| *game).ok_or(AppError::GameNotFound)
+    }
+
+    /// Waits up to `timeout` for any change of the game: a question, an
+    /// answer or its end.
+    pub async fn wait_for_change(&self, token: &Token, timeout: Duration) -> Result<(), AppError> {
+        let spectators = self.helpers.get(token).ok_or(AppError::GameNotFound)?.spectators.clone();
+        let _ = time::timeout(timeout, spectators.notified()).await;
+        Ok(())
+    }
+
+    fn notify_spectators(&self, token: &Token) {
+        if let Some(helper) = self.helpers.get(token) {
+            helper.spectators.notify_waiters();
+        }
+    }
+
+    /// Resolves once the game is deleted.
+    pub fn deleted(&self, token: &Token) -> Result<impl Future<Output = ()> + use<>, AppError> {
+        let mut alive = self.helpers.get(token).ok_or(AppError::GameNotFound)?.alive.subscribe();
+        Ok(async move {
+            while alive.changed().await.is_ok() {}
+        })
     }
 
-    #[allow(dead_code)]
     pub fn delete_game(&self, token: &Token) -> Result<(), AppError> {
         let notifier = self.get_notifier(token)?;
+        self.notify_spectators(token);
         self.game_states.remove(token);
-        self.helpers.remove(token);
+        if let Some((_, helper)) = self.helpers.remove(token) {
+            if let Some(spectator) = helper.spectator {
+                self.spectator_tokens.remove(&spectator);
+            }
+            if let Some(session) = helper.session {
+                self.forget_session_game(&session, token);
+            }
+        }
         notifier.notify_waiters();
         Ok(())
     }
 
+    /// Deletes the games older than `ttl`; their tokens aren't accepted anymore.
+    pub fn delete_expired(&self, ttl: Duration) {
+        let deadline = OffsetDateTime::now_utc().unix_timestamp() - ttl.as_secs() as i64;
+        let expired = self.helpers.iter()
+            .filter(|helper| helper.created <= deadline)
+            .map(|helper| *helper.key())
+            .collect::<Vec<_>>();
+        for token in expired {
+            info!("game {} expired", token);
+            let _ = self.delete_game(&token);
+        }
+    }
+
     #[allow(dead_code)]
     pub fn define_game_template(&self, template: &GameTemplate) -> Result<Token, AppError> {
         let token = Token::new(TokenType::GameTemplate);
@@ -92,23 +201,39 @@ impl GameManager {
     #[allow(dead_code)]
     pub fn new_game_from_template(&self, template_token: &Token) -> Result<Token, AppError> {
         let template = self.custom_games.get(template_token).ok_or(AppError::GameNotFound)?.deref().clone();
-        Ok(self.new_game(&template.identity, template.language, Some(template.properties)))
+        let token = self.new_game(&template.identity, template.language, Some(template.properties),
+                                  Difficulty::Normal, None, assets::current());
+        if let Some(mut helper) = self.helpers.get_mut(&token) {
+            helper.template = Some(*template_token);
+            helper.model_profile = template.model_profile;
+        }
+        Ok(token)
     }
 
-    pub fn new_game(&self, identity: &str, lang: Language, custom_info: Option<CustomGameInfo>) -> Token {
+    pub fn new_game(&self, identity: &str, lang: Language, custom_info: Option<CustomGameInfo>,
+                    difficulty: Difficulty, question_limit: Option<usize>, assets: Arc<Assets>) -> Token {
         let token = Token::new(TokenType::Game);
-        let mut game = GameState::default();
-        game.lang = lang.clone();
-        game.identity = Some(identity.to_string());
+        let variant = assets.pick_variant().to_string();
+        experiments().game_started(&variant);
+        let mut game = GameState {
+            lang: lang.clone(),
+            identity: Some(identity.to_string()),
+            variant: Some(variant.clone()),
+            difficulty,
+            question_limit,
+            ..Default::default()
+        };
 
         if let Some(custom_info) = custom_info {
             game.is_custom = true;
             game.custom_info = Some(custom_info);
         }
 
+        metrics().games_created.with_label_values(&[lang.to_code()]).inc();
         self.game_states.insert(token, game);
-        self.helpers.insert(token, StateHelper::default());
-        info!("*** New game: {}; [{}]; lang={}", token.to_string(), identity, lang.to_code());
+        info!("*** New game: {}; [{}]; lang={} difficulty={} assets={} variant={}", token.to_string(), identity,
+              lang.to_code(), difficulty.to_code(), assets.version, variant);
+        self.helpers.insert(token, StateHelper::new(assets));
         token
     }
 
@@ -117,6 +242,16 @@ impl GameManager {
         game.identity.clone().ok_or(AppError::InternalServerError)
     }
     
+    /// Custom game description; `None` for random games.
+    pub fn get_custom_info(&self, token: &Token) -> Result<Option<CustomGameInfo>, AppError> {
+        Ok(self.get_game(token)?.custom_info.clone())
+    }
+
+    /// Prompt variant the game was assigned at creation.
+    pub fn get_variant(&self, token: &Token) -> Result<Option<String>, AppError> {
+        Ok(self.get_game(token)?.variant.clone())
+    }
+
     pub fn get_language(&self, token: &Token) -> Result<Language, AppError> {
         let game = self.get_game(token)?;
         Ok(game.lang.clone())
@@ -129,10 +264,42 @@ impl GameManager {
             return Err(AppError::Pending);
         }
         game.pending_question = Some(Question { text: question.to_string() });
+        drop(game);
+        if let Some(mut helper) = self.helpers.get_mut(token) {
+            helper.guard_flagged = false;
+        }
+        self.notify_spectators(token);
         Ok(())
     }
 
+    /// Marks the record of the pending question as suspicious to the prompt guard.
+    pub fn flag_pending_question(&self, token: &Token) {
+        if let Some(mut helper) = self.helpers.get_mut(token) {
+            helper.guard_flagged = true;
+        }
+    }
+
     pub fn answer_pending_question(&self, token: &Token, answer: &Answer) -> Result<(), AppError> {
+        self.answer_pending_question_flagged(token, answer, &[])
+    }
+
+    /// Answers the pending question and marks records contradicted by the answer.
+    pub fn answer_pending_question_flagged(&self, token: &Token, answer: &Answer, conflicts: &[Conflict]) -> Result<(), AppError> {
+        self.publish_answer(token, answer, conflicts, false)
+    }
+
+    /// Answers the give-up question with the identity and ends the game.
+    pub fn give_up(&self, token: &Token, answer: &Answer) -> Result<(), AppError> {
+        if let Some(mut helper) = self.helpers.get_mut(token) {
+            helper.gave_up = true;
+        }
+        self.publish_answer(token, answer, &[], true)?;
+        self.finish_game(token)
+    }
+
+    fn publish_answer(&self, token: &Token, answer: &Answer, conflicts: &[Conflict], gave_up: bool) -> Result<(), AppError> {
+        let guard_flagged = self.helpers.get_mut(token)
+            .is_some_and(|mut helper| std::mem::take(&mut helper.guard_flagged));
         let mut game = self.get_game(token)?;
         let Some(pending_question) = game.pending_question.take() else {
             return Ok(());
@@ -141,18 +308,36 @@ impl GameManager {
         if answer.verdict == Some(Verdict::Final) {
             game.game_ended = true;
         }
+        metrics().count_verdict(&answer.verdict);
 
         let mut record = Record::new(pending_question.text);
         record.set_answer(answer);
+        record.guard_flagged = guard_flagged;
         game.add_record(record);
 
+        let count = game.records.len();
+        for conflict in conflicts.iter().filter(|c| c.first < count && c.second < count) {
+            game.records[conflict.first].add_conflict(conflict.second);
+            game.records[conflict.second].add_conflict(conflict.first);
+        }
+        if game.is_out_of_questions() {
+            info!("game {} ran out of questions", token);
+            game.game_ended = true;
+        }
+        let variant = game.variant.clone();
+
         // Don't lock the game and the notificator map simultaneously to prevent potential deadlocks.
         drop(game);
 
+        if let Some(variant) = variant {
+            experiments().answered(&variant, &answer.verdict, count, gave_up);
+        }
+
         // This is important to notify the client that the answer is ready.
         if let Ok(notifier) = self.get_notifier(token) {
             notifier.notify_one();
         }
+        self.notify_spectators(token);
 
         Ok(())
     }
@@ -161,9 +346,50 @@ impl GameManager {
         let mut game = self.get_game(token)?;
         game.error = Some(error);
         game.pending_question = None;
+        drop(game);
+
+        if let Ok(notifier) = self.get_notifier(token) {
+            notifier.notify_one();
+        }
+        self.notify_spectators(token);
         Ok(())
     }
 
+    pub fn get_assets(&self, token: &Token) -> Result<Arc<Assets>, AppError> {
+        Ok(self.helpers.get(token).ok_or(AppError::GameNotFound)?.assets.clone())
+    }
+
+    /// Profile of the model settings: the template's, or the difficulty level.
+    pub fn get_model_profile(&self, token: &Token) -> Result<Option<String>, AppError> {
+        let difficulty = self.get_difficulty(token)?;
+        let template_profile = self.helpers.get(token).and_then(|h| h.model_profile.clone());
+        Ok(template_profile.or_else(|| Some(difficulty.to_code().to_string())))
+    }
+
+    pub fn get_difficulty(&self, token: &Token) -> Result<Difficulty, AppError> {
+        Ok(self.get_game(token)?.difficulty)
+    }
+
+    /// Template the game was created from, if any.
+    pub fn get_template(&self, token: &Token) -> Option<Token> {
+        self.helpers.get(token)?.template
+    }
+
+    pub fn get_records(&self, token: &Token) -> Result<Vec<Record>, AppError> {
+        Ok(self.get_game(token)?.records.clone())
+    }
+
+    /// Consumes one consistency check from the game budget. Returns false when
+    /// the budget is exhausted.
+    pub fn take_consistency_check(&self, token: &Token, max_checks: u32) -> Result<bool, AppError> {
+        let mut helper = self.helpers.get_mut(token).ok_or(AppError::GameNotFound)?;
+        if helper.consistency_checks >= max_checks {
+            return Ok(false);
+        }
+        helper.consistency_checks += 1;
+        Ok(true)
+    }
+
     pub fn is_pending(&self, token: &Token) -> Result<bool, AppError> {
         Ok(self.get_game(token)?.pending_question.is_some())
     }
@@ -178,6 +404,8 @@ impl GameManager {
 
         if !game.game_ended {
             game.clear_comments();
+        } else if game.difficulty == Difficulty::Hard {
+            game.clear_comments_but_final();
         }
 
         Ok(game)
@@ -185,8 +413,120 @@ impl GameManager {
 
     pub fn finish_game(&self, token: &Token) -> Result<(), AppError> {
         self.get_game(token)?.game_ended = true;
+        self.notify_spectators(token);
         Ok(())
     }
+
+    /// Ends the game on operator request, dropping the pending question.
+    pub fn force_end_game(&self, token: &Token) -> Result<(), AppError> {
+        let mut game = self.get_game(token)?;
+        game.game_ended = true;
+        game.pending_question = None;
+        drop(game);
+        self.get_notifier(token)?.notify_waiters();
+        self.notify_spectators(token);
+        Ok(())
+    }
+
+    fn summary(&self, token: &Token, game: &GameState) -> AdminGameSummary {
+        AdminGameSummary {
+            token: token.to_string(),
+            lang: game.lang.clone(),
+            created: self.helpers.get(token).map(|h| h.created).unwrap_or_default(),
+            asset_version: self.helpers.get(token).map(|h| h.assets.version.clone()).unwrap_or_default(),
+            variant: game.variant.clone(),
+            questions: game.records.len(),
+            pending: game.pending_question.is_some(),
+            game_ended: game.game_ended,
+            is_custom: game.is_custom,
+        }
+    }
+
+    /// Adds the game to the history of the player session.
+    pub fn assign_session(&self, token: &Token, session: &Token) {
+        let Some(mut helper) = self.helpers.get_mut(token) else {
+            return;
+        };
+        helper.session = Some(*session);
+        drop(helper);
+        self.sessions.entry(*session).or_default().push(*token);
+    }
+
+    /// Drops the deleted game from the session, and the session once it has no games.
+    fn forget_session_game(&self, session: &Token, token: &Token) {
+        if let Some(mut games) = self.sessions.get_mut(session) {
+            games.retain(|game| game != token);
+        }
+        self.sessions.remove_if(session, |_, games| games.is_empty());
+    }
+
+    fn outcome(&self, token: &Token, game: &GameState) -> GameOutcome {
+        let solved = game.records.last()
+            .and_then(|r| r.answers.as_ref())
+            .is_some_and(|a| a.verdict == Some(Verdict::Final));
+        let gave_up = self.helpers.get(token).is_some_and(|h| h.gave_up);
+        if !game.game_ended {
+            GameOutcome::Active
+        } else if gave_up {
+            GameOutcome::GaveUp
+        } else if solved {
+            GameOutcome::Solved
+        } else if game.is_out_of_questions() {
+            GameOutcome::OutOfQuestions
+        } else {
+            GameOutcome::Ended
+        }
+    }
+
+    /// Games of the player session still in memory, the newest first.
+    pub fn history(&self, session: &Token) -> Vec<HistoryGame> {
+        let tokens = self.sessions.get(session).map(|games| games.clone()).unwrap_or_default();
+        tokens.iter().rev()
+            .filter_map(|token| {
+                let game = self.game_states.get(token)?.clone();
+                Some(HistoryGame {
+                    token: token.to_string(),
+                    created: self.helpers.get(token).map(|h| h.created).unwrap_or_default(),
+                    lang: game.lang.clone(),
+                    difficulty: game.difficulty,
+                    questions: game.records.len(),
+                    outcome: self.outcome(token, &game),
+                    is_custom: game.is_custom,
+                })
+            })
+            .collect()
+    }
+
+    /// All games in memory, the newest first.
+    pub fn list_games(&self) -> Vec<AdminGameSummary> {
+        let games = self.game_states.iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect::<Vec<_>>();
+        let mut list = games.iter()
+            .map(|(token, game)| self.summary(token, game))
+            .collect::<Vec<_>>();
+        list.sort_by_key(|game| std::cmp::Reverse(game.created));
+        list
+    }
+
+    pub fn inspect_game(&self, token: &Token) -> Result<AdminGameDetail, AppError> {
+        let game = self.get_game(token)?.clone();
+        Ok(AdminGameDetail {
+            summary: self.summary(token, &game),
+            identity: game.identity.clone().unwrap_or_default(),
+            template: self.get_template(token).map(|t| t.to_string()),
+            state: game,
+        })
+    }
+
+    pub fn list_templates(&self) -> Vec<AdminTemplate> {
+        self.custom_games.iter()
+            .map(|entry| AdminTemplate {
+                token: entry.key().to_string(),
+                template: entry.value().clone(),
+            })
+            .collect()
+    }
     
     pub fn is_game_active(&self, token: &Token) -> Result<bool, AppError> {
         let game = self.get_game(token)?;
@@ -1,7 +1,84 @@
+use serde::Deserialize;
+use serde_json::json;
+use tracing::warn;
+
+use std::sync::Arc;
+
 use crate::app_error::AppError;
-use crate::config::Config;
+use crate::assets::{self, Assets};
+use crate::prompt_template::Vars;
+use crate::config::{AnswerModel, Config};
+use crate::fact_sheet::FactSheet;
+use crate::usage::{Purpose, UsageTag};
 use crate::gpt::QuestionParams;
+use crate::locale::t;
 use shared::locale::Language;
+use shared::messages::{Answer, CustomGameInfo, Difficulty, Verdict};
+
+/// Verdict tokens the model is allowed to return, as listed in the instructions.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ReplyVerdict {
+    Yes,
+    No,
+    Unable,
+    Final,
+    Behave,
+}
+
+impl From<ReplyVerdict> for Verdict {
+    fn from(verdict: ReplyVerdict) -> Self {
+        match verdict {
+            ReplyVerdict::Yes => Verdict::Yes,
+            ReplyVerdict::No => Verdict::No,
+            ReplyVerdict::Unable => Verdict::Unable,
+            ReplyVerdict::Final => Verdict::Final,
+            ReplyVerdict::Behave => Verdict::Behave,
+        }
+    }
+}
+
+/// Structured model reply, see `reply_schema()`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct GameReply {
+    pub verdict: ReplyVerdict,
+    pub comment: String,
+}
+
+impl GameReply {
+    pub fn parse(text: &str) -> Option<Self> {
+        serde_json::from_str(text.trim()).ok()
+    }
+}
+
+/// Short fingerprint of the instructions, so answers cached with other
+/// instructions are not reused.
+pub fn prompt_version(instructions: &str) -> String {
+    let hash = instructions.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// The reply is the structured verdict the instructions ask for.
+pub fn is_valid_reply(text: &str) -> bool {
+    GameReply::parse(text).is_some()
+}
+
+fn reply_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "verdict": {
+                "type": "string",
+                "enum": ["YES", "NO", "UNABLE", "FINAL", "BEHAVE"]
+            },
+            "comment": { "type": "string" }
+        },
+        "required": ["verdict", "comment"],
+        "additionalProperties": false
+    })
+}
 
 
 #[derive(Debug, Clone)]
@@ -10,24 +87,84 @@ pub struct GameStepBuilder {
     question: Option<String>,
     target: Option<String>,
     language: Option<Language>,
+    consistency_note: Option<String>,
+    fact_sheet: Option<FactSheet>,
+    usage_tag: UsageTag,
+    assets: Arc<Assets>,
+    variant: Option<String>,
+    model_profile: Option<String>,
+    difficulty: Difficulty,
+    custom_info: Option<CustomGameInfo>,
 }
 
 
 impl GameStepBuilder {
+    /// Values of the instructions template variables.
+    fn template_vars(&self, config: &Config) -> Vars {
+        let flag = |on: bool| if on { "true".to_string() } else { String::new() };
+        let custom = self.custom_info.as_ref();
+        Vars::from([
+            ("target", self.target.clone().unwrap()),
+            ("language", self.language.clone().unwrap().to_instruction().to_string()),
+            ("custom", flag(custom.is_some())),
+            ("comment", custom.and_then(|c| c.comment.clone()).unwrap_or_default().trim().to_string()),
+            ("hints", flag(config.gpt.hints || self.difficulty == Difficulty::Easy)),
+            ("difficulty", self.difficulty.to_code().to_string()),
+            ("easy", flag(self.difficulty == Difficulty::Easy)),
+            ("hard", flag(self.difficulty == Difficulty::Hard)),
+            ("reask", flag(self.consistency_note.is_some())),
+        ])
+    }
+
     pub fn build_params(&self, config: &Config) -> QuestionParams {
         let mut params = QuestionParams::default();
-        let target = self.target.clone().unwrap();
         let language = self.language.clone().unwrap();
 
+        params.set_instructions(self.instructions(&language, &self.template_vars(config)));
+        params.set_answer_model(&self.get_answer_model(config));
+        params.set_json_schema("guess_who_reply", reply_schema());
+        params.set_usage_tag(self.usage_tag.clone());
+        params
+    }
 
-        let instructions =
-            config.gpt.gpt_instructions
-                .replace("{target}", &target.as_str())
-                .replace("{language}", language.to_instruction());
+    /// Turns the model output into the game answer. Falls back to the legacy
+    /// "TOKEN; comment" parser when the reply isn't the expected JSON.
+    pub fn parse_answer(&self, text: &str) -> Answer {
+        if let Some(reply) = GameReply::parse(text) {
+            return Answer::from_verdict(reply.verdict.into(), &reply.comment);
+        }
+        warn!("structured reply expected, falling back to legacy parser: {}", text);
+        let language = self.language.clone().unwrap_or_default();
+        Answer::parse_from_string(text, &t(&language, "game.weird_question"))
+    }
 
+    /// Instructions of the variant with the fact sheet, without the consistency note.
+    fn instructions(&self, language: &Language, vars: &Vars) -> String {
+        let mut instructions = self.assets.instructions(self.variant.as_deref(), language).render(vars);
+        if let Some(fact_sheet) = &self.fact_sheet {
+            instructions.push_str("\n\nKnown facts about your identity, keep your answers consistent with them:\n");
+            instructions.push_str(&fact_sheet.to_prompt());
+        }
+        instructions
+    }
 
-        params.set_instructions(instructions);
-        params
+    /// Fingerprint of the rendered instructions and the fact sheet; the
+    /// identity is left out of the template, so the answer cache is shared
+    /// by all games of the same kind.
+    pub fn get_prompt_version(&self, config: &Config) -> String {
+        let language = self.language.clone().unwrap_or_default();
+        let mut vars = self.template_vars(config);
+        vars.insert("target", String::new());
+        prompt_version(&self.instructions(&language, &vars))
+    }
+
+    /// Model settings of the answer, with the profile of the game applied.
+    pub fn get_answer_model(&self, config: &Config) -> AnswerModel {
+        config.answer.with_profile(self.model_profile.as_deref())
+    }
+
+    pub fn get_model_profile(&self) -> Option<&str> {
+        self.model_profile.as_deref()
     }
 
     pub fn new(_config: &Config) -> Self {
@@ -36,6 +173,14 @@ impl GameStepBuilder {
             question: None,
             target: None,
             language: None,
+            consistency_note: None,
+            fact_sheet: None,
+            usage_tag: UsageTag::new(Purpose::Answer),
+            assets: assets::current(),
+            variant: None,
+            model_profile: None,
+            difficulty: Difficulty::Normal,
+            custom_info: None,
         }
     }
 
@@ -50,7 +195,7 @@ impl GameStepBuilder {
 
 
     pub fn set_question(mut self, question: &str) -> Self {
-        if let Ok(q) = shared::gpt::sanitize_question(&question.to_string()) {
+        if let Ok(q) = shared::gpt::sanitize_question(question) {
             self.question = Some(q);
             self.original_question = Some(question.to_string());
             return self;
@@ -58,6 +203,53 @@ impl GameStepBuilder {
         self
     }
 
+    pub fn set_fact_sheet(mut self, fact_sheet: Option<FactSheet>) -> Self {
+        self.fact_sheet = fact_sheet;
+        self
+    }
+
+    /// Assets of the game; the current ones are used by default.
+    pub fn set_assets(mut self, assets: Arc<Assets>) -> Self {
+        self.assets = assets;
+        self
+    }
+
+    /// Prompt variant of the game; the first one of the assets by default.
+    pub fn set_variant(mut self, variant: Option<String>) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Profile of `[answer.profiles]` overriding the model settings.
+    pub fn set_model_profile(mut self, profile: Option<String>) -> Self {
+        self.model_profile = profile;
+        self
+    }
+
+    pub fn set_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    pub fn set_custom_info(mut self, custom_info: Option<CustomGameInfo>) -> Self {
+        self.custom_info = custom_info;
+        self
+    }
+
+    pub fn set_usage_tag(mut self, usage_tag: UsageTag) -> Self {
+        self.usage_tag = usage_tag;
+        self
+    }
+
+    pub fn get_usage_tag(&self) -> UsageTag {
+        self.usage_tag.clone()
+    }
+
+    pub fn set_consistency_note(mut self, note: &str) -> Self {
+        self.consistency_note = Some(note.to_string());
+        self
+    }
+
     pub fn set_language(mut self, language: &Language) -> Self {
         self.language = Some(language.clone());
         self
@@ -74,8 +266,14 @@ impl GameStepBuilder {
         self.original_question.clone().unwrap()
     }
 
+    /// Input of the model: the sanitized question, preceded by the earlier
+    /// answers it contradicted when asked again.
     pub fn build_question(&self) -> String {
-        self.question.clone().unwrap()
+        let question = self.question.clone().unwrap();
+        match &self.consistency_note {
+            Some(note) => format!("{}\n{}", note, question),
+            None => question,
+        }
     }
 
     #[allow(dead_code)]
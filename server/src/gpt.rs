@@ -1,12 +1,19 @@
 
 
+use std::time::{Duration, Instant};
+
 use anyhow::{anyhow, Context, Result};
-use log::{error, info};
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use log::{error, info, warn};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use crate::{config, string_enum};
+use crate::key_ring::get_key_ring;
+use crate::rate_limit;
+use crate::experiments::experiments;
+use crate::metrics::metrics;
+use crate::usage::{self, Purpose, UsageTag};
 
 string_enum! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -43,10 +50,37 @@ pub enum OutputItem {
 }
 
 
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct InputTokensDetails {
+    #[serde(default)]
+    pub cached_tokens: u64,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct OutputTokensDetails {
+    #[serde(default)]
+    pub reasoning_tokens: u64,
+}
+
+/// Token usage of a single call; `output_tokens` include the reasoning tokens.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Usage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub input_tokens_details: InputTokensDetails,
+    #[serde(default)]
+    pub output_tokens_details: OutputTokensDetails,
+}
+
 #[derive(Deserialize)]
 pub struct Response {
     #[serde(default)]
     output: Vec<OutputItem>,
+    #[serde(default)]
+    usage: Option<Usage>,
 }
 
 impl Response {
@@ -75,28 +109,48 @@ impl Answer {
         self.response.first_output_text_typed().map(ToString::to_string)
     }
 
+    pub fn usage(&self) -> Option<&Usage> {
+        self.response.usage.as_ref()
+    }
+
 }
 
 pub struct GptClient {
     client: reqwest::Client,
-    key: String,
+    queue_timeout: Duration,
+}
+
+string_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Verbosity {
+        Low => "low",
+        Medium => "medium",
+        High => "high",
+    }
 }
 
 string_enum! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    enum Verbosity {
+    pub enum ReasoningEffort {
+        Minimal => "minimal",
         Low => "low",
         Medium => "medium",
         High => "high",
     }
 }
 
+#[derive(Clone)]
 pub struct QuestionParams {
     verbosity: Verbosity,
     model: Model,
+    /// Model asked by `ask_with_fallback` when `model` fails.
+    fallback_model: Option<Model>,
+    reasoning_effort: Option<ReasoningEffort>,
     instructions: Option<String>,
     max_output_tokens: Option<i32>,
     temperature: Option<f32>,
+    json_schema: Option<(String, serde_json::Value)>,
+    usage_tag: UsageTag,
 }
 
 impl Default for QuestionParams {
@@ -104,9 +158,13 @@ impl Default for QuestionParams {
         Self {
             verbosity: Verbosity::Medium,
             model: Model::Gpt5Nano,
+            fallback_model: None,
+            reasoning_effort: None,
             instructions: None,
             max_output_tokens: None,
             temperature: None,
+            json_schema: None,
+            usage_tag: UsageTag::new(Purpose::Answer),
         }
     }
 }
@@ -118,6 +176,57 @@ impl QuestionParams {
             self.instructions = Some(s.to_owned());
         }
     }
+
+    pub fn set_model(&mut self, model: Model) {
+        self.model = model;
+    }
+
+    pub fn set_usage_tag(&mut self, usage_tag: UsageTag) {
+        self.usage_tag = usage_tag;
+    }
+
+    /// Model, fallback, verbosity, reasoning and limits of `[answer]`;
+    /// invalid names, reported by `check-config`, keep the defaults.
+    pub fn set_answer_model(&mut self, settings: &config::AnswerModel) {
+        let parse = |what: &str, name: &str| -> Option<Model> {
+            name.parse().inspect_err(|_| warn!("unknown {} \"{}\"", what, name)).ok()
+        };
+        if let Some(model) = parse("model", &settings.model) {
+            self.model = model;
+        }
+        self.fallback_model = settings.fallback_model.as_deref()
+            .and_then(|name| parse("fallback model", name))
+            .filter(|model| *model != self.model);
+        if let Ok(verbosity) = settings.verbosity.parse() {
+            self.verbosity = verbosity;
+        }
+        self.reasoning_effort = settings.reasoning_effort.as_deref().and_then(|e| e.parse().ok());
+        self.max_output_tokens = settings.max_output_tokens;
+        self.temperature = settings.temperature;
+    }
+
+    pub fn set_max_output_tokens(&mut self, max_output_tokens: Option<i32>) {
+        self.max_output_tokens = max_output_tokens;
+    }
+
+    /// Requests structured output; the model reply is then a JSON document
+    /// conforming to `schema`.
+    pub fn set_json_schema(&mut self, name: &str, schema: serde_json::Value) {
+        self.json_schema = Some((name.to_string(), schema));
+    }
+
+    fn text_options(&self) -> serde_json::Value {
+        let mut text = json!({ "verbosity": self.verbosity.to_string() });
+        if let Some((name, schema)) = &self.json_schema {
+            text["format"] = json!({
+                "type": "json_schema",
+                "name": name,
+                "schema": schema,
+                "strict": true,
+            });
+        }
+        text
+    }
 }
 
 #[derive(Serialize)]
@@ -127,6 +236,8 @@ struct RequestBody<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     instructions: Option<&'a str>,
     text: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning: Option<serde_json::Value>,
     max_output_tokens: Option<i32>,
     temperature: Option<f32>,
 }
@@ -135,14 +246,10 @@ impl GptClient {
     pub fn new(config: &config::Gpt) -> Self {
         Self {
             client: reqwest::Client::new(),
-            key: config.gpt_key.clone(),
+            queue_timeout: Duration::from_secs(config.queue_timeout_secs),
         }
     }
 
-    fn get_key(&self) -> &str {
-        &self.key
-    }
-
     pub async fn ask(&self, question: &str, params: &QuestionParams) -> Result<Answer> {
         info!("Asking...");
         let body = RequestBody {
@@ -151,32 +258,74 @@ impl GptClient {
             temperature: params.temperature,
             instructions: params.instructions.as_deref(),
             max_output_tokens: params.max_output_tokens,
-            text: json!({ "verbosity": params.verbosity.to_string() }),
+            text: params.text_options(),
+            reasoning: params.reasoning_effort.map(|effort| json!({ "effort": effort.as_str() })),
         };
         let body = serde_json::to_value(&body)?;
         
+        let model = params.model.as_str();
+        rate_limit::check_model_call()?;
+        let key = get_key_ring().lease(self.queue_timeout).await?;
+        let started = Instant::now();
         let resp = self.client
             .post("https://api.openai.com/v1/responses")
             .header(CONTENT_TYPE, "application/json")
-            .header(AUTHORIZATION, format!("Bearer {}", self.get_key()))
+            .header(AUTHORIZATION, format!("Bearer {}", key.secret()))
             .json(&body)
             .send()
             .await
             .map_err(|err| {
                 error!("OpenAI error: {}", err);
+                metrics().gpt_requests.with_label_values(&[model, "transport"]).inc();
                 anyhow!("OpenAI error: {}", err)
             })?;
 
         let status = resp.status();
+        let retry_after = resp.headers().get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .map(Duration::from_secs);
+        key.report_status(status, retry_after);
+        metrics().gpt_requests.with_label_values(&[model, status.as_str()]).inc();
+        metrics().gpt_latency
+            .with_label_values(&[model, params.usage_tag.purpose.as_str()])
+            .observe(started.elapsed().as_secs_f64());
         let bytes = resp.bytes().await.context("Reading body failed")?;
         
         if !status.is_success() {
             let text = String::from_utf8_lossy(&bytes);
-            error!("OpenAI error {}: {}", status, text);
+            error!("OpenAI error {} (key {}): {}", status, key.name(), text);
             anyhow::bail!("OpenAI error {}: {}", status, text);
         }
 
-        Answer::from_bytes(&bytes)
+        let answer = Answer::from_bytes(&bytes)?;
+        if let Some(usage) = answer.usage() {
+            let cost = usage::record(params.model, &params.usage_tag, usage);
+            key.add_cost(cost);
+            if let Some(variant) = &params.usage_tag.variant {
+                experiments().add_cost(variant, cost);
+            }
+        }
+        Ok(answer)
+    }
+
+    /// Asks the fallback model of `params` when the call fails or `valid`
+    /// rejects the reply text.
+    pub async fn ask_with_fallback(&self, question: &str, params: &QuestionParams,
+                                   valid: impl Fn(&str) -> bool) -> Result<Answer> {
+        let result = self.ask(question, params).await;
+        let Some(fallback) = params.fallback_model else {
+            return result;
+        };
+        match &result {
+            Ok(answer) if answer.to_string().is_some_and(|text| valid(&text)) => return result,
+            Ok(_) => warn!("unusable reply of {}, asking {}", params.model, fallback),
+            Err(err) => warn!("{} failed, asking {}: {}", params.model, fallback, err),
+        }
+        let mut params = params.clone();
+        params.model = fallback;
+        params.fallback_model = None;
+        self.ask(question, &params).await
     }
 }
 
@@ -0,0 +1,373 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use time::OffsetDateTime;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::config::{ApiKey, Config};
+
+/// How long a key rests after the API refused it as unauthorized.
+const UNAUTHORIZED_COOLDOWN: Duration = Duration::from_secs(600);
+/// How long a key rests after 429 when the reply has no `Retry-After`.
+const RATE_LIMITED_COOLDOWN: Duration = Duration::from_secs(60);
+/// How often a lease waiting for a key re-checks the cooldowns.
+const LEASE_RECHECK: Duration = Duration::from_millis(500);
+
+struct KeyState {
+    name: String,
+    secret: String,
+    max_concurrent: Option<usize>,
+    daily_limit_usd: Option<f64>,
+    in_flight: AtomicUsize,
+    cooldown_until: Mutex<Option<Instant>>,
+    /// Spend of the current day ("2025-09-30"); kept in memory only.
+    spend: Mutex<(String, f64)>,
+}
+
+fn today() -> String {
+    let date = OffsetDateTime::now_utc().date();
+    format!("{:04}-{:02}-{:02}", date.year(), date.month() as u8, date.day())
+}
+
+impl KeyState {
+    fn is_available(&self, now: Instant) -> bool {
+        if self.cooldown_until.lock().unwrap().is_some_and(|until| until > now) {
+            return false;
+        }
+        if self.max_concurrent.is_some_and(|max| self.in_flight.load(Ordering::Relaxed) >= max) {
+            return false;
+        }
+        let Some(limit) = self.daily_limit_usd else {
+            return true;
+        };
+        let spend = self.spend.lock().unwrap();
+        spend.0 != today() || spend.1 < limit
+    }
+
+    /// In-flight calls relative to the capacity; the least loaded key is used.
+    fn load(&self) -> f64 {
+        let in_flight = self.in_flight.load(Ordering::Relaxed) as f64;
+        match self.max_concurrent {
+            Some(max) => in_flight / max.max(1) as f64,
+            None => in_flight / 1000.0,
+        }
+    }
+}
+
+/// A key reserved for one call; released on drop.
+pub struct KeyLease {
+    key: Arc<KeyState>,
+    ring: &'static KeyRing,
+}
+
+impl KeyLease {
+    pub fn secret(&self) -> &str {
+        &self.key.secret
+    }
+
+    pub fn name(&self) -> &str {
+        &self.key.name
+    }
+
+    /// Takes the key out of rotation after the API refused it.
+    pub fn report_status(&self, status: reqwest::StatusCode, retry_after: Option<Duration>) {
+        let cooldown = match status.as_u16() {
+            401 | 403 => UNAUTHORIZED_COOLDOWN,
+            429 => retry_after.unwrap_or(RATE_LIMITED_COOLDOWN),
+            _ => return,
+        };
+        warn!("API key {} refused with {}, resting for {:?}", self.key.name, status, cooldown);
+        *self.key.cooldown_until.lock().unwrap() = Some(Instant::now() + cooldown);
+    }
+
+    pub fn add_cost(&self, cost_usd: f64) {
+        let today = today();
+        let mut spend = self.key.spend.lock().unwrap();
+        if spend.0 != today {
+            *spend = (today, 0.0);
+        }
+        spend.1 += cost_usd;
+        if self.key.daily_limit_usd.is_some_and(|limit| spend.1 >= limit) {
+            warn!("API key {} reached its daily limit", self.key.name);
+        }
+    }
+}
+
+impl Drop for KeyLease {
+    fn drop(&mut self) {
+        self.key.in_flight.fetch_sub(1, Ordering::Relaxed);
+        self.ring.released.notify_one();
+    }
+}
+
+/// The OpenAI API keys; calls are spread over the available keys.
+pub struct KeyRing {
+    config: Config,
+    keys: RwLock<Vec<Arc<KeyState>>>,
+    released: Notify,
+}
+
+impl KeyRing {
+    fn new(config: &Config) -> Result<Self> {
+        let ring = Self {
+            config: config.clone(),
+            keys: RwLock::new(Vec::new()),
+            released: Notify::new(),
+        };
+        ring.reload()?;
+        Ok(ring)
+    }
+
+    fn read_keys(&self, source: &ApiKey, index: usize) -> Result<Vec<KeyState>> {
+        let path = self.config.get_key_path(source);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read key file {:?}: {}", path, e))?;
+        let base = source.name.clone().unwrap_or_else(|| format!("key{}", index));
+        let secrets = content.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        let many = secrets.len() > 1;
+        Ok(secrets.into_iter()
+            .enumerate()
+            .map(|(i, secret)| KeyState {
+                name: if many { format!("{}#{}", base, i) } else { base.clone() },
+                secret: secret.to_string(),
+                max_concurrent: source.max_concurrent,
+                daily_limit_usd: source.daily_limit_usd,
+                in_flight: AtomicUsize::new(0),
+                cooldown_until: Mutex::new(None),
+                spend: Mutex::new((String::new(), 0.0)),
+            })
+            .collect())
+    }
+
+    /// Re-reads the key files. Keys which are still present keep their
+    /// cooldown and spend; the old set stays if no key can be read.
+    pub fn reload(&self) -> Result<()> {
+        let mut loaded = Vec::new();
+        for (index, source) in self.config.get_api_keys().iter().enumerate() {
+            match self.read_keys(source, index) {
+                Ok(keys) => loaded.extend(keys),
+                Err(err) => warn!("{}", err),
+            }
+        }
+        if loaded.is_empty() {
+            bail!("no OpenAI API key configured");
+        }
+
+        let mut keys = self.keys.write().unwrap();
+        let fresh = loaded.into_iter()
+            .map(|key| {
+                let old = keys.iter().find(|old| old.secret == key.secret);
+                if let Some(old) = old {
+                    *key.cooldown_until.lock().unwrap() = *old.cooldown_until.lock().unwrap();
+                    *key.spend.lock().unwrap() = old.spend.lock().unwrap().clone();
+                }
+                Arc::new(key)
+            })
+            .collect::<Vec<_>>();
+        info!("loaded {} API keys: {}", fresh.len(),
+            fresh.iter().map(|k| k.name.as_str()).collect::<Vec<_>>().join(", "));
+        *keys = fresh;
+        drop(keys);
+        self.released.notify_waiters();
+        Ok(())
+    }
+
+    fn try_lease(&'static self) -> Option<KeyLease> {
+        let now = Instant::now();
+        let keys = self.keys.read().unwrap();
+        let key = keys.iter()
+            .filter(|key| key.is_available(now))
+            .min_by(|a, b| a.load().total_cmp(&b.load()))?;
+        key.in_flight.fetch_add(1, Ordering::Relaxed);
+        Some(KeyLease { key: key.clone(), ring: self })
+    }
+
+    /// Reserves the least loaded available key, waiting up to `timeout` for
+    /// one to free up or to come back from a cooldown.
+    pub async fn lease(&'static self, timeout: Duration) -> Result<KeyLease> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            if let Some(lease) = self.try_lease() {
+                return Ok(lease);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                bail!("no OpenAI API key available");
+            }
+            let _ = tokio::time::timeout(remaining.min(LEASE_RECHECK), released).await;
+        }
+    }
+}
+
+static KEY_RING: OnceLock<KeyRing> = OnceLock::new();
+
+pub fn init_key_ring(config: &Config) -> Result<()> {
+    if KEY_RING.get().is_none() {
+        let _ = KEY_RING.set(KeyRing::new(config)?);
+    }
+    Ok(())
+}
+
+pub fn get_key_ring() -> &'static KeyRing {
+    KEY_RING.get().expect("Key ring not initialized. Call init_key_ring() first.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use reqwest::StatusCode;
+
+    /// Key file in the temp directory, unique to the test.
+    fn key_file(test: &str, secrets: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("gggame-keys-{}-{}", std::process::id(), test));
+        std::fs::write(&path, secrets.join("\n")).unwrap();
+        path
+    }
+
+    /// Ring over `[[gpt.keys]]` entries given as TOML tables.
+    fn ring(keys: &[String]) -> &'static KeyRing {
+        let config: Config = toml::from_str(&format!(
+            "debug = false\n[www]\nport = 3000\n[gpt]\nmax_clients_count = 1\n[dirs]\n{}",
+            keys.iter().map(|key| format!("[[gpt.keys]]\n{}\n", key)).collect::<String>())).unwrap();
+        Box::leak(Box::new(KeyRing::new(&config).unwrap()))
+    }
+
+    fn key(name: &str, file: &Path, extra: &str) -> String {
+        format!("name = \"{}\"\nkey_file = {:?}\n{}", name, file.to_str().unwrap(), extra)
+    }
+
+    fn state(ring: &KeyRing, secret: &str) -> Arc<KeyState> {
+        ring.keys.read().unwrap().iter().find(|key| key.secret == secret).unwrap().clone()
+    }
+
+    fn cooldown(ring: &KeyRing, secret: &str) -> Option<Duration> {
+        let until = *state(ring, secret).cooldown_until.lock().unwrap();
+        until.map(|until| until.saturating_duration_since(Instant::now()))
+    }
+
+    #[test]
+    fn refused_keys_rest() {
+        let file = key_file("refused", &["sk-a"]);
+        let ring = ring(&[key("a", &file, "")]);
+
+        for status in [StatusCode::UNAUTHORIZED, StatusCode::FORBIDDEN] {
+            *state(ring, "sk-a").cooldown_until.lock().unwrap() = None;
+            ring.try_lease().unwrap().report_status(status, None);
+            let rest = cooldown(ring, "sk-a").unwrap();
+            assert!(rest > UNAUTHORIZED_COOLDOWN - Duration::from_secs(5) && rest <= UNAUTHORIZED_COOLDOWN);
+            assert!(ring.try_lease().is_none());
+        }
+
+        *state(ring, "sk-a").cooldown_until.lock().unwrap() = None;
+        ring.try_lease().unwrap().report_status(StatusCode::TOO_MANY_REQUESTS, None);
+        let rest = cooldown(ring, "sk-a").unwrap();
+        assert!(rest > RATE_LIMITED_COOLDOWN - Duration::from_secs(5) && rest <= RATE_LIMITED_COOLDOWN);
+
+        *state(ring, "sk-a").cooldown_until.lock().unwrap() = None;
+        ring.try_lease().unwrap().report_status(StatusCode::TOO_MANY_REQUESTS, Some(Duration::from_secs(7)));
+        let rest = cooldown(ring, "sk-a").unwrap();
+        assert!(rest > Duration::from_secs(2) && rest <= Duration::from_secs(7));
+
+        *state(ring, "sk-a").cooldown_until.lock().unwrap() = None;
+        ring.try_lease().unwrap().report_status(StatusCode::INTERNAL_SERVER_ERROR, None);
+        assert_eq!(cooldown(ring, "sk-a"), None);
+        assert!(ring.try_lease().is_some());
+    }
+
+    #[test]
+    fn keys_over_daily_limit_are_skipped() {
+        let limited = key_file("limited", &["sk-limited"]);
+        let other = key_file("other", &["sk-other"]);
+        let ring = ring(&[key("limited", &limited, "daily_limit_usd = 1.0"), key("other", &other, "")]);
+
+        let lease = ring.try_lease().unwrap();
+        assert_eq!(lease.name(), "limited");
+        lease.add_cost(0.4);
+        drop(lease);
+        assert_eq!(ring.try_lease().unwrap().name(), "limited");
+
+        let lease = ring.try_lease().unwrap();
+        lease.add_cost(0.6);
+        drop(lease);
+        assert_eq!(ring.try_lease().unwrap().name(), "other");
+
+        // the spend of another day does not count
+        state(ring, "sk-limited").spend.lock().unwrap().0 = "2000-01-01".to_string();
+        assert_eq!(ring.try_lease().unwrap().name(), "limited");
+    }
+
+    #[test]
+    fn reload_keeps_state_of_present_keys() {
+        let file = key_file("reload", &["sk-a", "sk-b"]);
+        let ring = ring(&[key("main", &file, "")]);
+
+        let lease = ring.try_lease().unwrap();
+        assert_eq!(lease.name(), "main#0");
+        lease.add_cost(0.25);
+        lease.report_status(StatusCode::TOO_MANY_REQUESTS, None);
+        drop(lease);
+        ring.try_lease().unwrap().add_cost(0.5);
+
+        std::fs::write(&file, "sk-c\nsk-a\n").unwrap();
+        ring.reload().unwrap();
+
+        let names = ring.keys.read().unwrap().iter().map(|key| key.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names, ["main#0", "main#1"]);
+        let kept = state(ring, "sk-a");
+        assert_eq!(kept.spend.lock().unwrap().1, 0.25);
+        assert!(cooldown(ring, "sk-a").is_some_and(|rest| !rest.is_zero()));
+        let fresh = state(ring, "sk-c");
+        assert_eq!(fresh.spend.lock().unwrap().1, 0.0);
+        assert!(fresh.cooldown_until.lock().unwrap().is_none());
+        assert!(ring.keys.read().unwrap().iter().all(|key| key.secret != "sk-b"));
+    }
+
+    #[test]
+    fn failed_reload_keeps_old_keys() {
+        let file = key_file("failed-reload", &["sk-a"]);
+        let ring = ring(&[key("a", &file, "")]);
+
+        std::fs::write(&file, "# no keys\n").unwrap();
+        assert!(ring.reload().is_err());
+        assert_eq!(ring.try_lease().unwrap().secret(), "sk-a");
+    }
+
+    #[tokio::test]
+    async fn lease_times_out_without_free_key() {
+        let file = key_file("timeout", &["sk-a"]);
+        let ring = ring(&[key("a", &file, "max_concurrent = 1")]);
+
+        let busy = ring.lease(Duration::from_millis(50)).await.unwrap();
+        let waited = Instant::now();
+        let result = tokio::time::timeout(Duration::from_secs(2), ring.lease(Duration::from_millis(50))).await;
+        assert!(result.expect("lease did not give up").is_err());
+        assert!(waited.elapsed() < LEASE_RECHECK);
+
+        drop(busy);
+        assert!(ring.lease(Duration::from_millis(50)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn waiter_gets_released_key() {
+        let file = key_file("waiter", &["sk-a"]);
+        let ring = ring(&[key("a", &file, "max_concurrent = 1")]);
+
+        let busy = ring.lease(Duration::from_millis(50)).await.unwrap();
+        let waiter = tokio::spawn(ring.lease(Duration::from_secs(5)));
+        tokio::task::yield_now().await;
+        drop(busy);
+        let lease = tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        assert_eq!(lease.unwrap().name(), "a");
+    }
+}
@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 use shared::locale::{Language, TranslationInserter, Translations};
+use shared::messages::Difficulty;
 use crate::config::Config;
 
 #[derive(Default, Clone, Debug)]
@@ -35,34 +36,67 @@ impl Identities {
 pub struct LocaleManager {
     translations: Translations,
     identities: HashMap<Language, Identities>,
+    /// Identities of easy and hard games, when the assets have them.
+    pools: HashMap<(Language, Difficulty), Identities>,
+    /// Raw contents of the translation override files.
+    overrides: HashMap<Language, String>,
 }
 
 impl LocaleManager {
-    pub fn new(config: &Config) -> Self {
+    /// Built-in translations, overridden by `translations_<lang>.toml` of the
+    /// assets when present, and the identity catalogs.
+    pub fn load(config: &Config) -> Result<Self, anyhow::Error> {
         let mut manager = Self {
             translations: Translations::new(),
             identities: HashMap::new(),
+            pools: HashMap::new(),
+            overrides: HashMap::new(),
         };
-        
+
         manager.load_english();
         manager.load_czech();
 
-        // Load identities for each language
-        if let Ok(english_identities) = Identities::read(config.get_identities_file(&Language::English)) {
-            manager.identities.insert(Language::English, english_identities);
-        } else {
-            log::warn!("Failed to load English identities, using empty list");
-            manager.identities.insert(Language::English, Identities::default());
-        }
+        for lang in manager.available_languages() {
+            let identities = Identities::read(config.get_identities_file(&lang))?;
+            manager.identities.insert(lang.clone(), identities);
+            for difficulty in [Difficulty::Easy, Difficulty::Hard] {
+                let path = config.get_difficulty_identities_file(&lang, difficulty);
+                if path.exists() {
+                    manager.pools.insert((lang.clone(), difficulty), Identities::read(path)?);
+                }
+            }
 
-        if let Ok(czech_identities) = Identities::read(config.get_identities_file(&Language::Czech)) {
-            manager.identities.insert(Language::Czech, czech_identities);
-        } else {
-            log::warn!("Failed to load Czech identities, using empty list");
-            manager.identities.insert(Language::Czech, Identities::default());
+            let path = config.get_translations_file(&lang);
+            if !path.exists() {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read translations {:?}: {}", path, e))?;
+            let table = toml::from_str::<toml::Table>(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid translations {:?}: {}", path, e))?;
+            let mut i = TranslationInserter::new(lang.clone(), &mut manager.translations);
+            for (key, value) in &table {
+                let Some(value) = value.as_str() else {
+                    anyhow::bail!("Translation {} in {:?} is not a string", key, path);
+                };
+                i.add(key, value);
+            }
+            manager.overrides.insert(lang, content);
         }
 
-        manager
+        Ok(manager)
+    }
+
+    /// Everything loaded from files for the language, for the assets version.
+    pub fn contents(&self, lang: &Language) -> String {
+        let identities = self.identities.get(lang).map(|i| i.list.join("\n")).unwrap_or_default();
+        let pools = [Difficulty::Easy, Difficulty::Hard].iter()
+            .filter_map(|d| self.pools.get(&(lang.clone(), *d)))
+            .map(|i| i.list.join("\n"))
+            .collect::<Vec<_>>()
+            .join("\0");
+        let overrides = self.overrides.get(lang).cloned().unwrap_or_default();
+        format!("{}\0{}\0{}\0{}", lang.to_code(), identities, pools, overrides)
     }
     
     fn load_english(&mut self) {
@@ -70,17 +104,42 @@ impl LocaleManager {
 
         // User-facing error messages only
         i.add("error.invalid_token", "invalid token");
+        i.add("error.wrong_token_type", "this token is not meant for this request");
         i.add("error.pending", "pending");
         i.add("error.game_not_found", "game not found");
         i.add("error.invalid_input", "invalid input");
         i.add("error.internal_server_error", "internal server error");
         i.add("error.timeout", "timeout");
         i.add("error.not_found", "Not found");
+        i.add("error.inactive_game", "The game is over");
+        i.add("error.game_in_progress", "The game isn't over yet");
+        i.add("error.spectator_only", "Spectators can only watch the game");
+        i.add("error.invalid_game_template", "invalid game template");
+        i.add("error.unauthorized", "unauthorized");
+        i.add("error.rate_limited", "Too many requests, try again in {} s");
+        i.add("error.server_busy", "The server is busy, ask again in a while.");
+        i.add("error.shutting_down", "The server is restarting, ask again in a while.");
+        i.add("error.budget_exceeded", "The game is closed for now, the budget ran out. Come back later.");
 
         // Game responses (user-facing)
         i.add("game.final_answer", "I'm {}");
         i.add("game.weird_question", "Weird question, skip...");
-        i.add("game.gpt_fallback", "UNABLE; this is weird");
+        i.add("game.behave", "Nice try, but I play by the rules.");
+        i.add("game.gpt_fallback", "This is weird.");
+
+        // Exported transcripts
+        i.add("transcript.title", "Guess Who – game transcript");
+        i.add("transcript.difficulty", "Difficulty: {}");
+        i.add("transcript.questions", "{} questions");
+        i.add("difficulty.easy", "Easy");
+        i.add("difficulty.normal", "Normal");
+        i.add("difficulty.hard", "Hard");
+        i.add("verdict.yes", "Yes");
+        i.add("verdict.no", "No");
+        i.add("verdict.unable", "Unable");
+        i.add("verdict.final", "Final");
+        i.add("verdict.behave", "Behave");
+        i.add("verdict.na", "N/A");
 
         // Cheat detection phrases (user input matching)
         i.add("cheat.im_loser", "IM LOSER");
@@ -93,17 +152,42 @@ impl LocaleManager {
 
         // User-facing error messages only
         i.add("error.invalid_token", "neplatný token");
+        i.add("error.wrong_token_type", "tento token nepatří k tomuto požadavku");
         i.add("error.pending", "čeká se");
         i.add("error.game_not_found", "hra nenalezena");
         i.add("error.invalid_input", "neplatný vstup");
         i.add("error.internal_server_error", "vnitřní chyba serveru");
         i.add("error.timeout", "časový limit");
         i.add("error.not_found", "Nenalezeno");
+        i.add("error.inactive_game", "Hra už skončila");
+        i.add("error.game_in_progress", "Hra ještě neskončila");
+        i.add("error.spectator_only", "Divák může hru jen sledovat");
+        i.add("error.invalid_game_template", "neplatné zadání hry");
+        i.add("error.unauthorized", "nepovolený přístup");
+        i.add("error.rate_limited", "Příliš mnoho požadavků, zkus to znovu za {} s");
+        i.add("error.server_busy", "Server je přetížený, zeptej se znovu za chvíli.");
+        i.add("error.shutting_down", "Server se restartuje, zeptej se znovu za chvíli.");
+        i.add("error.budget_exceeded", "Hra je teď zavřená, došel rozpočet. Přijď později.");
 
         // Game responses (user-facing)
         i.add("game.final_answer", "Jsem {}");
         i.add("game.weird_question", "Podivná otázka, přeskočit...");
-        i.add("game.gpt_fallback", "NEMOHU; to je divné");
+        i.add("game.behave", "Pěkný pokus, ale já hraju podle pravidel.");
+        i.add("game.gpt_fallback", "To je divné.");
+
+        // Exported transcripts
+        i.add("transcript.title", "Hádej kdo – záznam hry");
+        i.add("transcript.difficulty", "Obtížnost: {}");
+        i.add("transcript.questions", "Otázek: {}");
+        i.add("difficulty.easy", "Lehká");
+        i.add("difficulty.normal", "Normální");
+        i.add("difficulty.hard", "Těžká");
+        i.add("verdict.yes", "Ano");
+        i.add("verdict.no", "Ne");
+        i.add("verdict.unable", "Nelze");
+        i.add("verdict.final", "Konec");
+        i.add("verdict.behave", "Nezlob!");
+        i.add("verdict.na", "N/A");
 
         // Cheat detection phrases (user input matching)
         i.add("cheat.im_loser", "JSEM PORAŽENÝ");
@@ -145,8 +229,13 @@ impl LocaleManager {
         self.identities.get(lang)
     }
     
-    pub fn get_random_identity(&self, lang: &Language) -> Option<String> {
-        let identities = self.identities.get(lang)?;
+    /// Identities of the difficulty; the normal catalog without a separate one.
+    pub fn get_identities_for(&self, lang: &Language, difficulty: Difficulty) -> Option<&Identities> {
+        self.pools.get(&(lang.clone(), difficulty)).or_else(|| self.identities.get(lang))
+    }
+
+    pub fn get_random_identity(&self, lang: &Language, difficulty: Difficulty) -> Option<String> {
+        let identities = self.get_identities_for(lang, difficulty)?;
         if identities.list.is_empty() {
             log::warn!("No identities found for language {:?}", lang);
             None
@@ -158,31 +247,74 @@ impl LocaleManager {
     }
 }
 
-// Global locale manager instance using std::sync::OnceLock for thread safety
-static LOCALE_MANAGER: std::sync::OnceLock<LocaleManager> = std::sync::OnceLock::new();
-
-pub fn init_locale(config: &Config) {
-    LOCALE_MANAGER.get_or_init(|| LocaleManager::new(config));
+// Convenience functions; they use the current assets
+pub fn t(lang: &Language, key: &str) -> String {
+    crate::assets::current().locale.get(lang, key)
 }
 
-pub fn get_locale_manager() -> &'static LocaleManager {
-    LOCALE_MANAGER.get().expect("Locale manager not initialized. Call init_locale() first.")
+pub fn tf(lang: &Language, key: &str, args: &[&str]) -> String {
+    crate::assets::current().locale.get_formatted(lang, key, args)
 }
 
+tokio::task_local! {
+    // Language of the request being handled, used to localize error replies.
+    static REQUEST_LANGUAGE: Language;
+}
 
-// Convenience functions
-pub fn t(lang: &Language, key: &str) -> String {
-    get_locale_manager().get(lang, key)
+pub async fn with_request_language<F: std::future::Future>(lang: Language, f: F) -> F::Output {
+    REQUEST_LANGUAGE.scope(lang, f).await
 }
 
-pub fn tf(lang: &Language, key: &str, args: &[&str]) -> String {
-    get_locale_manager().get_formatted(lang, key, args)
+pub fn request_language() -> Language {
+    REQUEST_LANGUAGE.try_with(|lang| lang.clone()).unwrap_or_default()
 }
 
-pub fn get_random_identity(lang: &Language) -> Option<String> {
-    get_locale_manager().get_random_identity(lang)
+/// Most preferred supported language of an `Accept-Language` header value,
+/// e.g. "cs-CZ,cs;q=0.9,en;q=0.8".
+pub fn parse_accept_language(header: &str) -> Option<Language> {
+    let mut langs = header.split(',')
+        .filter_map(|item| {
+            let mut parts = item.trim().split(';');
+            let tag = parts.next()?.trim();
+            let q = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+            let lang = Language::from_str(tag.split('-').next()?)?;
+            Some((lang, q))
+        })
+        .filter(|(_, q)| *q > 0.0)
+        .collect::<Vec<_>>();
+    langs.sort_by(|a, b| b.1.total_cmp(&a.1));
+    langs.into_iter().next().map(|(lang, _)| lang)
 }
 
-pub fn get_identities(lang: &Language) -> Option<&'static Identities> {
-    get_locale_manager().get_identities(lang)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_language_prefers_the_highest_q() {
+        assert_eq!(parse_accept_language("cs-CZ,cs;q=0.9,en;q=0.8"), Some(Language::Czech));
+        assert_eq!(parse_accept_language("cs;q=0.5, en-US;q=0.7"), Some(Language::English));
+        assert_eq!(parse_accept_language("en;q=0.3,cs"), Some(Language::Czech));
+    }
+
+    #[test]
+    fn accept_language_skips_refused_languages() {
+        assert_eq!(parse_accept_language("en;q=0,cs;q=0.1"), Some(Language::Czech));
+        assert_eq!(parse_accept_language("cs;q=0"), None);
+    }
+
+    #[test]
+    fn accept_language_skips_unknown_tags() {
+        assert_eq!(parse_accept_language("de-DE,fr;q=0.9,cs;q=0.2"), Some(Language::Czech));
+        assert_eq!(parse_accept_language("*;q=0.5,en;q=0.1"), Some(Language::English));
+        assert_eq!(parse_accept_language("de,fr"), None);
+        assert_eq!(parse_accept_language(""), None);
+    }
+
+    #[test]
+    fn accept_language_skips_malformed_q() {
+        assert_eq!(parse_accept_language("cs;q=high,en;q=0.5"), Some(Language::English));
+    }
 }
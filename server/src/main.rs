@@ -1,7 +1,9 @@
+use std::fs;
 use std::path::PathBuf;
+use std::process::ExitCode;
 use std::sync::Arc;
-use std::{env, fs};
 use anyhow::Result;
+use clap::{Parser, Subcommand};
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
@@ -19,9 +21,24 @@ mod game_manager;
 mod app_error;
 mod client_pool;
 mod token_gen;
+mod typed_token;
 mod game_prompt;
 mod config;
 mod locale;
+mod prompt_guard;
+mod text;
+mod consistency;
+mod fact_sheet;
+mod answer_cache;
+mod usage;
+mod rate_limit;
+mod metrics;
+mod key_ring;
+mod tls;
+mod assets;
+mod prompt_template;
+mod experiments;
+mod transcript;
 
 struct GptClientFactory {
     config: Gpt,
@@ -46,34 +63,75 @@ impl PollableClientFactory<GptClient> for GptClientFactory {
 }
 
 
-fn read_config() -> Result<Config> {
-    let config = {
-        #[cfg(not(debug_assertions))]
-        {
-            let args: Vec<String> = env::args().collect();
-            let config_path = args.get(1).ok_or_else(|| {
-                anyhow::anyhow!("Config file path must be passed as first argument")
-            })?;
-            Config::read(config_path)?
-        }
-        #[cfg(debug_assertions)]
-        {
-            let config_file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+/// Guess Who game server.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// Config file; `assets/config.toml` of the source tree in debug builds.
+    #[arg(short, long, global = true, env = config::ENV_CONFIG)]
+    config: Option<PathBuf>,
+    /// Config file, the way it used to be passed: `gggame <config>`.
+    #[arg(hide = true)]
+    config_path: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs the server (the default).
+    Serve,
+    /// Reports every problem of the config and exits.
+    CheckConfig,
+    /// Prints a documented config file.
+    PrintDefaultConfig,
+}
+
+const DEFAULT_CONFIG: &str = include_str!("../assets/config-release.toml");
+
+impl Cli {
+    fn config_path(&self) -> Result<String> {
+        let path = match self.config.as_ref().or(self.config_path.as_ref()) {
+            Some(path) => path.clone(),
+            #[cfg(debug_assertions)]
+            None => PathBuf::from(env!("CARGO_MANIFEST_DIR"))
                 .join("assets")
-                .join("config.toml");
-            let config_path = config_file.to_str().ok_or_else(|| {
-                anyhow::anyhow!("Invalid UTF-8 in config path")
-            })?;
-            Config::read(config_path)?
-        }
-    };
+                .join("config.toml"),
+            #[cfg(not(debug_assertions))]
+            None => anyhow::bail!("Config file must be passed with --config or {}", config::ENV_CONFIG),
+        };
+        path.to_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Invalid UTF-8 in config path"))
+    }
+}
 
-    Ok(config)
+fn check_config(path: &str) -> Result<ExitCode> {
+    let config = Config::load(path)?;
+    let problems = config.problems();
+    if problems.is_empty() {
+        println!("{}: OK", path);
+        return Ok(ExitCode::SUCCESS);
+    }
+    eprintln!("{}: {} problems", path, problems.len());
+    for problem in problems {
+        eprintln!("  {}", problem);
+    }
+    Ok(ExitCode::FAILURE)
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let config = read_config()?;
+async fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    match cli.command.as_ref().unwrap_or(&Command::Serve) {
+        Command::Serve => (),
+        Command::CheckConfig => return check_config(&cli.config_path()?),
+        Command::PrintDefaultConfig => {
+            print!("{}", DEFAULT_CONFIG);
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
+    let config = Config::read(&cli.config_path()?)?;
     
     // Initialize logging based on config
     let env_filter = EnvFilter::try_from_default_env()
@@ -104,10 +162,14 @@ async fn main() -> Result<()> {
     }
     
     // Initialize locale system
-    locale::init_locale(&config);
+    assets::init_assets(&config)?;
+    usage::init_usage(&config);
+    rate_limit::init_model_call_limit(&config);
+    key_ring::init_key_ring(&config)?;
+    token_gen::init_tokens(&config)?;
     
     run_server(&config, Arc::new(GptClientFactory::new(&config))).await?;
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 
 //  cargo build --release --target x86_64-unknown-linux-musl
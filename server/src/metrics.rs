@@ -0,0 +1,105 @@
+use std::sync::OnceLock;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+use shared::messages::Verdict;
+
+/// Prometheus metrics of the server, scraped at `/metrics`.
+pub struct Metrics {
+    registry: Registry,
+    pub games_created: IntCounterVec,
+    pub questions: IntCounterVec,
+    pub verdicts: IntCounterVec,
+    pub give_ups: IntCounter,
+    pub solves: IntCounter,
+    pub gpt_latency: HistogramVec,
+    pub gpt_requests: IntCounterVec,
+    pub pool_clients: IntGauge,
+    pub pool_idle: IntGauge,
+    pub pool_waiters: IntGauge,
+    pub pool_rejected: IntCounterVec,
+    pub long_poll: IntCounterVec,
+    pub answer_cache: IntCounterVec,
+    pub answer_cache_entries: IntGauge,
+}
+
+fn counter(registry: &Registry, name: &str, help: &str) -> IntCounter {
+    let c = IntCounter::new(name, help).unwrap();
+    registry.register(Box::new(c.clone())).unwrap();
+    c
+}
+
+fn counter_vec(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    let c = IntCounterVec::new(Opts::new(name, help), labels).unwrap();
+    registry.register(Box::new(c.clone())).unwrap();
+    c
+}
+
+fn gauge(registry: &Registry, name: &str, help: &str) -> IntGauge {
+    let g = IntGauge::new(name, help).unwrap();
+    registry.register(Box::new(g.clone())).unwrap();
+    g
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let r = Registry::new_custom(Some("gggame".to_string()), None).unwrap();
+
+        let gpt_latency = HistogramVec::new(
+            HistogramOpts::new("gpt_request_duration_seconds", "Latency of model calls")
+                .buckets(vec![0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0]),
+            &["model", "purpose"],
+        ).unwrap();
+        r.register(Box::new(gpt_latency.clone())).unwrap();
+
+        Self {
+            games_created: counter_vec(&r, "games_created_total", "Games created", &["lang"]),
+            questions: counter_vec(&r, "questions_total", "Questions accepted", &["lang"]),
+            verdicts: counter_vec(&r, "answers_total", "Answers published, by verdict", &["verdict"]),
+            give_ups: counter(&r, "give_ups_total", "Games given up by the player"),
+            solves: counter(&r, "solves_total", "Games solved by the player (FINAL verdict)"),
+            gpt_latency,
+            gpt_requests: counter_vec(&r, "gpt_requests_total",
+                "Model calls by HTTP status; \"transport\" when no response arrived", &["model", "status"]),
+            pool_clients: gauge(&r, "pool_clients", "Model clients created by the pool"),
+            pool_idle: gauge(&r, "pool_idle_clients", "Model clients waiting in the pool"),
+            pool_waiters: gauge(&r, "pool_waiters", "Requests waiting for a model client"),
+            pool_rejected: counter_vec(&r, "pool_rejected_total",
+                "Requests refused a model client", &["reason"]),
+            long_poll: counter_vec(&r, "long_poll_total", "Outcomes of waiting for an answer", &["outcome"]),
+            answer_cache: counter_vec(&r, "answer_cache_lookups_total", "Answer cache lookups", &["result"]),
+            answer_cache_entries: gauge(&r, "answer_cache_entries", "Entries in the answer cache"),
+            registry: r,
+        }
+    }
+
+    pub fn count_verdict(&self, verdict: &Option<Verdict>) {
+        let label = match verdict {
+            Some(Verdict::Yes) => "yes",
+            Some(Verdict::No) => "no",
+            Some(Verdict::Behave) => "behave",
+            Some(Verdict::Unable) => "unable",
+            Some(Verdict::Final) => "final",
+            Some(Verdict::NotSet) | None => "not_set",
+            Some(Verdict::Pending) => "pending",
+        };
+        self.verdicts.with_label_values(&[label]).inc();
+    }
+
+    /// Text exposition format of all metrics.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(err) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::warn!("metrics not encoded: {}", err);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
@@ -0,0 +1,309 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{anyhow, bail, Result};
+
+/// Variables the game provides to the instructions.
+pub const VARIABLES: &[&str] = &["target", "language", "custom", "comment", "hints", "difficulty", "easy", "hard", "reask"];
+
+/// Nesting limit of `{% include %}`, which also stops include cycles.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Values of the template variables; a condition holds for a non-empty value.
+pub type Vars = HashMap<&'static str, String>;
+
+#[derive(Debug, Clone)]
+enum Token {
+    Text(String),
+    Var(String),
+    If { name: String, negate: bool },
+    Else,
+    EndIf,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Var(String),
+    If { name: String, negate: bool, then: Vec<Node>, otherwise: Vec<Node> },
+}
+
+/// Instructions template:
+/// - `{name}` is replaced with the variable,
+/// - `{% if name %}...{% else %}...{% endif %}` keeps one of the branches;
+///   `{% if not name %}` negates the condition,
+/// - `{% include "file.txt" %}` inserts another template of the assets.
+///
+/// Other braces, e.g. JSON examples, are plain text.
+#[derive(Debug, Clone)]
+pub struct Template {
+    nodes: Vec<Node>,
+    /// The template and all included files, for fingerprinting.
+    source: String,
+}
+
+fn is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+struct Tokenizer<'a> {
+    include: &'a mut dyn FnMut(&str) -> Result<String>,
+    tokens: Vec<Token>,
+    source: String,
+}
+
+impl Tokenizer<'_> {
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.tokens.last_mut() {
+            Some(Token::Text(last)) => last.push_str(text),
+            _ => self.tokens.push(Token::Text(text.to_string())),
+        }
+    }
+
+    fn tokenize(&mut self, file: &str, src: &str, depth: usize) -> Result<()> {
+        self.source.push_str(src);
+        let mut rest = src;
+        while let Some(start) = rest.find('{') {
+            self.push_text(&rest[..start]);
+            let after = &rest[start..];
+
+            if let Some(tag) = after.strip_prefix("{%") {
+                let end = tag.find("%}")
+                    .ok_or_else(|| anyhow!("{}: unterminated tag `{}`", file, first_line(after)))?;
+                let line_start = match self.tokens.last() {
+                    Some(Token::Text(text)) => text.ends_with('\n'),
+                    Some(Token::Var(_)) => false,
+                    _ => true,
+                };
+                self.tag(file, tag[..end].trim(), depth)?;
+                rest = &tag[end + 2..];
+                // a tag on its own line doesn't leave an empty line behind
+                if line_start {
+                    rest = rest.strip_prefix('\n').unwrap_or(rest);
+                }
+                continue;
+            }
+
+            match after[1..].find('}') {
+                Some(end) if is_name(&after[1..end + 1]) => {
+                    self.tokens.push(Token::Var(after[1..end + 1].to_string()));
+                    rest = &after[end + 2..];
+                }
+                _ => {
+                    self.push_text("{");
+                    rest = &after[1..];
+                }
+            }
+        }
+        self.push_text(rest);
+        Ok(())
+    }
+
+    fn tag(&mut self, file: &str, tag: &str, depth: usize) -> Result<()> {
+        let mut words = tag.split_whitespace();
+        match (words.next(), words.next(), words.next(), words.next()) {
+            (Some("if"), Some("not"), Some(name), None) if is_name(name) => {
+                self.tokens.push(Token::If { name: name.to_string(), negate: true });
+            }
+            (Some("if"), Some(name), None, None) if is_name(name) => {
+                self.tokens.push(Token::If { name: name.to_string(), negate: false });
+            }
+            (Some("else"), None, None, None) => self.tokens.push(Token::Else),
+            (Some("endif"), None, None, None) => self.tokens.push(Token::EndIf),
+            (Some("include"), Some(name), None, None) => {
+                let name = name.trim_matches('"');
+                if depth >= MAX_INCLUDE_DEPTH {
+                    bail!("{}: includes nested too deep at `{}`", file, name);
+                }
+                let src = (self.include)(name)?;
+                self.tokenize(name, &src, depth + 1)?;
+            }
+            _ => bail!("{}: unknown tag `{{% {} %}}`", file, tag),
+        }
+        Ok(())
+    }
+}
+
+fn first_line(s: &str) -> &str {
+    s.lines().next().unwrap_or_default()
+}
+
+fn build(tokens: &mut std::vec::IntoIter<Token>, nested: bool) -> Result<(Vec<Node>, Option<Token>)> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text)),
+            Token::Var(name) => nodes.push(Node::Var(name)),
+            Token::If { name, negate } => {
+                let (then, end) = build(tokens, true)?;
+                let otherwise = match end {
+                    Some(Token::Else) => match build(tokens, true)? {
+                        (otherwise, Some(Token::EndIf)) => otherwise,
+                        _ => bail!("`{{% else %}}` of `{}` without `{{% endif %}}`", name),
+                    },
+                    Some(Token::EndIf) => Vec::new(),
+                    _ => bail!("`{{% if {} %}}` without `{{% endif %}}`", name),
+                };
+                nodes.push(Node::If { name, negate, then, otherwise });
+            }
+            Token::Else | Token::EndIf if nested => return Ok((nodes, Some(token))),
+            Token::Else => bail!("`{{% else %}}` without `{{% if %}}`"),
+            Token::EndIf => bail!("`{{% endif %}}` without `{{% if %}}`"),
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn collect_names<'a>(nodes: &'a [Node], names: &mut BTreeSet<&'a str>) {
+    for node in nodes {
+        match node {
+            Node::Text(_) => (),
+            Node::Var(name) => {
+                names.insert(name);
+            }
+            Node::If { name, then, otherwise, .. } => {
+                names.insert(name);
+                collect_names(then, names);
+                collect_names(otherwise, names);
+            }
+        }
+    }
+}
+
+fn render_nodes(nodes: &[Node], vars: &Vars, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => out.push_str(vars.get(name.as_str()).map_or("", String::as_str)),
+            Node::If { name, negate, then, otherwise } => {
+                let holds = vars.get(name.as_str()).is_some_and(|v| !v.is_empty());
+                render_nodes(if holds != *negate { then } else { otherwise }, vars, out);
+            }
+        }
+    }
+}
+
+impl Template {
+    /// Parses the template; `include` reads the files of `{% include %}`.
+    /// Fails on malformed tags and on variables not in `VARIABLES`.
+    pub fn parse(file: &str, src: &str, include: &mut dyn FnMut(&str) -> Result<String>) -> Result<Self> {
+        let mut tokenizer = Tokenizer { include, tokens: Vec::new(), source: String::new() };
+        tokenizer.tokenize(file, src, 0)?;
+        let (nodes, _) = build(&mut tokenizer.tokens.into_iter(), false)
+            .map_err(|err| anyhow!("{}: {}", file, err))?;
+        let template = Self { nodes, source: tokenizer.source };
+
+        let unknown = template.names().into_iter()
+            .filter(|name| !VARIABLES.contains(name))
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            bail!("{}: unknown variables {}; known are {}", file, unknown.join(", "), VARIABLES.join(", "));
+        }
+        Ok(template)
+    }
+
+    /// Variables used in placeholders and conditions.
+    pub fn names(&self) -> BTreeSet<&str> {
+        let mut names = BTreeSet::new();
+        collect_names(&self.nodes, &mut names);
+        names
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn render(&self, vars: &Vars) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, vars, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_include(name: &str) -> Result<String> {
+        bail!("no file {}", name)
+    }
+
+    fn parse(src: &str) -> Result<Template> {
+        Template::parse("test.txt", src, &mut no_include)
+    }
+
+    fn vars(pairs: &[(&'static str, &str)]) -> Vars {
+        pairs.iter().map(|(k, v)| (*k, v.to_string())).collect()
+    }
+
+    #[test]
+    fn renders_variables() {
+        let template = parse("You are {target}, answer in {language}.").unwrap();
+        let out = template.render(&vars(&[("target", "Einstein"), ("language", "English")]));
+        assert_eq!(out, "You are Einstein, answer in English.");
+        assert_eq!(template.names().into_iter().collect::<Vec<_>>(), ["language", "target"]);
+    }
+
+    #[test]
+    fn missing_variable_renders_empty() {
+        assert_eq!(parse("[{comment}]").unwrap().render(&Vars::new()), "[]");
+    }
+
+    #[test]
+    fn other_braces_are_text() {
+        let src = r#"Reply {"verdict": "YES"} or { target } or {Target}."#;
+        assert_eq!(parse(src).unwrap().render(&vars(&[("target", "x")])), src);
+    }
+
+    #[test]
+    fn conditions() {
+        let template = parse("{% if easy %}easy{% else %}not easy{% endif %}/{% if not hints %}no hints{% endif %}").unwrap();
+        assert_eq!(template.render(&vars(&[("easy", "true")])), "easy/no hints");
+        assert_eq!(template.render(&vars(&[("easy", ""), ("hints", "true")])), "not easy/");
+    }
+
+    #[test]
+    fn tag_lines_leave_no_empty_lines() {
+        let template = parse("a\n{% if custom %}\nb\n{% endif %}\nc\n").unwrap();
+        assert_eq!(template.render(&vars(&[("custom", "true")])), "a\nb\nc\n");
+        assert_eq!(template.render(&Vars::new()), "a\nc\n");
+    }
+
+    #[test]
+    fn includes() {
+        let mut include = |name: &str| match name {
+            "rules.txt" => Ok("Rules for {target}.".to_string()),
+            _ => bail!("no file {}", name),
+        };
+        let template = Template::parse("main.txt", "Start. {% include \"rules.txt\" %} End.", &mut include).unwrap();
+        assert_eq!(template.render(&vars(&[("target", "Curie")])), "Start. Rules for Curie. End.");
+        assert!(template.source().contains("Rules for"));
+    }
+
+    #[test]
+    fn include_cycle_fails() {
+        let mut include = |_: &str| Ok("{% include \"self.txt\" %}".to_string());
+        let err = Template::parse("main.txt", "{% include \"self.txt\" %}", &mut include).unwrap_err();
+        assert!(err.to_string().contains("nested too deep"));
+    }
+
+    #[test]
+    fn unknown_variables_fail() {
+        let err = parse("{target} {secret} {% if nope %}{% endif %}").unwrap_err().to_string();
+        assert!(err.contains("unknown variables nope, secret"), "{}", err);
+    }
+
+    #[test]
+    fn malformed_tags_fail() {
+        assert!(parse("{% if easy %}no end").is_err());
+        assert!(parse("{% else %}").is_err());
+        assert!(parse("{% endif %}").is_err());
+        assert!(parse("{% if easy %}{% else %}").is_err());
+        assert!(parse("{% loop %}").is_err());
+        assert!(parse("{% if easy").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tracing::info;
+
+use shared::token::Token;
+
+use crate::app_error::AppError;
+use crate::config::{Bucket, Config, RateLimit};
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn full(bucket: &Bucket) -> Self {
+        Self {
+            tokens: bucket.burst as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, bucket: &Bucket, now: Instant) {
+        let per_sec = bucket.per_minute as f64 / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_sec).min(bucket.burst as f64);
+        self.updated = now;
+    }
+
+    /// Takes one token, or returns how long it takes until one is available.
+    fn take(&mut self, bucket: &Bucket) -> Result<(), Duration> {
+        self.refill(bucket, Instant::now());
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        if bucket.per_minute == 0 {
+            return Err(Duration::from_secs(60));
+        }
+        let missing = 1.0 - self.tokens;
+        Err(Duration::from_secs_f64(missing * 60.0 / bucket.per_minute as f64))
+    }
+
+    fn is_full(&self, bucket: &Bucket, now: Instant) -> bool {
+        let mut probe = self.clone();
+        probe.refill(bucket, now);
+        probe.tokens >= bucket.burst as f64
+    }
+}
+
+fn take_keyed<K: Eq + Hash>(buckets: &DashMap<K, TokenBucket>, key: K, bucket: &Bucket) -> Result<(), Duration> {
+    buckets.entry(key)
+        .or_insert_with(|| TokenBucket::full(bucket))
+        .take(bucket)
+}
+
+fn limited(what: &str, who: &str, retry_after: Duration) -> AppError {
+    let retry_after = retry_after.as_secs() + 1;
+    info!(target: "rate_limit", "{} limit hit by {}, retry after {}s", what, who, retry_after);
+    AppError::RateLimited(retry_after)
+}
+
+/// Token buckets limiting game creation per IP and questions per game.
+pub struct RateLimiter {
+    config: RateLimit,
+    new_game: DashMap<IpAddr, TokenBucket>,
+    question: DashMap<Token, TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.rate_limit.clone(),
+            new_game: DashMap::new(),
+            question: DashMap::new(),
+        }
+    }
+
+    pub fn check_new_game(&self, ip: IpAddr) -> Result<(), AppError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        take_keyed(&self.new_game, ip, &self.config.new_game)
+            .map_err(|d| limited("new game", &ip.to_string(), d))
+    }
+
+    pub fn check_question(&self, token: &Token) -> Result<(), AppError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        take_keyed(&self.question, *token, &self.config.question)
+            .map_err(|d| limited("question", &token.to_string(), d))
+    }
+
+    /// Drops buckets which have refilled completely; they are recreated full
+    /// on demand anyway.
+    pub fn prune(&self) {
+        let now = Instant::now();
+        self.new_game.retain(|_, b| !b.is_full(&self.config.new_game, now));
+        self.question.retain(|_, b| !b.is_full(&self.config.question, now));
+    }
+}
+
+/// Bucket of the model calls of the whole server.
+struct ModelCallLimiter {
+    config: RateLimit,
+    bucket: Mutex<TokenBucket>,
+}
+
+// Global, so every model call is charged wherever it's made.
+static MODEL_CALL_LIMITER: OnceLock<ModelCallLimiter> = OnceLock::new();
+
+pub fn init_model_call_limit(config: &Config) {
+    MODEL_CALL_LIMITER.get_or_init(|| ModelCallLimiter {
+        config: config.rate_limit.clone(),
+        bucket: Mutex::new(TokenBucket::full(&config.rate_limit.model)),
+    });
+}
+
+/// Takes one model call from the server bucket; `GptClient::ask` calls it
+/// before every request.
+pub fn check_model_call() -> Result<(), AppError> {
+    let Some(limiter) = MODEL_CALL_LIMITER.get() else {
+        return Ok(());
+    };
+    if !limiter.config.enabled {
+        return Ok(());
+    }
+    limiter.bucket.lock().unwrap()
+        .take(&limiter.config.model)
+        .map_err(|d| limited("model call", "server", d))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(new_game: Bucket, question: Bucket) -> RateLimiter {
+        RateLimiter {
+            config: RateLimit { new_game, question, ..RateLimit::default() },
+            new_game: DashMap::new(),
+            question: DashMap::new(),
+        }
+    }
+
+    #[test]
+    fn bucket_allows_the_burst_then_limits() {
+        let bucket = Bucket { burst: 3, per_minute: 1 };
+        let mut tokens = TokenBucket::full(&bucket);
+        for _ in 0..3 {
+            assert!(tokens.take(&bucket).is_ok());
+        }
+        let retry_after = tokens.take(&bucket).unwrap_err();
+        assert!(retry_after > Duration::from_secs(50) && retry_after <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn bucket_refills_with_time() {
+        let bucket = Bucket { burst: 2, per_minute: 60 };
+        let mut tokens = TokenBucket::full(&bucket);
+        tokens.tokens = 0.0;
+        let later = tokens.updated + Duration::from_millis(1500);
+        tokens.refill(&bucket, later);
+        assert!((tokens.tokens - 1.5).abs() < 1e-9);
+        tokens.refill(&bucket, later + Duration::from_secs(10));
+        assert_eq!(tokens.tokens, 2.0);
+        assert!(tokens.is_full(&bucket, later + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn empty_bucket_without_refill_waits_a_minute() {
+        let bucket = Bucket { burst: 0, per_minute: 0 };
+        let mut tokens = TokenBucket::full(&bucket);
+        assert_eq!(tokens.take(&bucket), Err(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn buckets_are_per_ip() {
+        let limiter = limiter(Bucket { burst: 1, per_minute: 1 }, Bucket { burst: 1, per_minute: 1 });
+        let first: IpAddr = "10.0.0.1".parse().unwrap();
+        let second: IpAddr = "10.0.0.2".parse().unwrap();
+        assert!(limiter.check_new_game(first).is_ok());
+        assert!(matches!(limiter.check_new_game(first), Err(AppError::RateLimited(_))));
+        assert!(limiter.check_new_game(second).is_ok());
+    }
+
+    #[test]
+    fn disabled_limiter_allows_everything() {
+        let mut limiter = limiter(Bucket { burst: 0, per_minute: 0 }, Bucket { burst: 0, per_minute: 0 });
+        limiter.config.enabled = false;
+        for _ in 0..10 {
+            assert!(limiter.check_new_game("10.0.0.1".parse().unwrap()).is_ok());
+        }
+    }
+
+    #[test]
+    fn prune_drops_full_buckets_only() {
+        let limiter = limiter(Bucket { burst: 2, per_minute: 1 }, Bucket { burst: 1, per_minute: 1 });
+        limiter.check_new_game("10.0.0.1".parse().unwrap()).unwrap();
+        limiter.new_game.insert("10.0.0.2".parse().unwrap(), TokenBucket::full(&limiter.config.new_game));
+        limiter.prune();
+        assert_eq!(limiter.new_game.len(), 1);
+    }
+}
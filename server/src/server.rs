@@ -1,6 +1,7 @@
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -8,14 +9,16 @@ use anyhow::{Context, Result};
 use axum::{
     body::Bytes,
     extract::{ConnectInfo, Path, Query, Request, State},
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap},
     middleware::{self, Next},
     response::{IntoResponse, Redirect, Response},
     routing::{get, post},
-    Router,
+    Json, Router,
 };
 use serde::Deserialize;
-use tokio::{net::TcpListener, sync::Mutex};
+use axum::serve::ListenerExt;
+use dashmap::DashMap;
+use tokio::{net::TcpListener, sync::{watch, Mutex, Notify}, task::JoinSet};
 use tower::ServiceBuilder;
 use tower_http::{
     classify::{ServerErrorsAsFailures, SharedClassifier},
@@ -23,25 +26,41 @@ use tower_http::{
     trace::{DefaultMakeSpan, DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, TraceLayer},
 };
 use tracing::{info, warn, Level};
+use utoipa::OpenApi;
 
 use crate::{
+    answer_cache::{AnswerCache, CacheKey},
     app_error::*,
     client_pool::*,
+    consistency::ConsistencyChecker,
+    fact_sheet::FactSheetStore,
     game_manager::*,
-    game_prompt::GameStepBuilder,
+    assets::{self, Assets},
+    experiments::experiments,
+    game_prompt::{is_valid_reply, GameStepBuilder},
+    metrics::metrics,
     gpt::*,
+    prompt_guard::PromptGuard,
+    rate_limit::RateLimiter,
+    tls::{load_tls_config, TlsListener},
     token_gen::TokenGen,
+    typed_token::{parse_token, GameToken, SessionToken, SpectatorToken, TemplateToken},
+    transcript::{self, TranscriptFormat},
+    usage::{self, Purpose, UsageReport, UsageTag},
     Config,
 };
 use shared::{
-    messages::{status_response, GameError, ServerResponse, Status},
+    messages::{
+        status_response, AdminAssets, AdminGameDetail, AdminGameSummary, AdminTemplate, AdminVariantStats, AskRequest, Difficulty, ErrorCode, ErrorResponse, GameError, GameState, HistoryGame, NewGameRequest,
+        ServerResponse, Status, StatusResponse, TokenResponse, Transcript, Verdict,
+    },
     token::*,
 };
 use serde::de::Deserializer;
 use shared::locale::Language;
 use shared::messages::{GameTemplate, GameTemplateStatus};
 use crate::config::DirType;
-use crate::locale::t;
+use crate::locale::{parse_accept_language, t, tf, with_request_language};
 
 fn de_opt_bool<'de, D>(deserializer: D) -> Result<i32, D::Error>
 where
@@ -60,18 +79,23 @@ where
 struct NewGameParam {
     #[serde(default)]
     lang: Option<String>,
+    #[serde(default)]
+    difficulty: Option<String>,
 }
 
 impl NewGameParam {
     fn get_language(&self) -> Language {
         match &self.lang {
             Some(lang) => {
-                Language::from_str(&lang).unwrap_or_else(||
-                    Language::English)
+                Language::from_str(lang).unwrap_or(Language::English)
             },
             None => Language::English,
         }
     }
+
+    fn get_difficulty(&self) -> Difficulty {
+        self.difficulty.as_deref().and_then(Difficulty::from_code).unwrap_or_default()
+    }
 }
 
 
@@ -98,6 +122,15 @@ struct AppState {
     client_factory: Arc<ClientsPool::<GptClient>>,
     config: Config,
     game_manager: GameManager,
+    prompt_guard: PromptGuard,
+    consistency: ConsistencyChecker,
+    fact_sheets: FactSheetStore,
+    answer_cache: AnswerCache,
+    rate_limiter: RateLimiter,
+    /// Games whose question is being answered.
+    answering: DashMap<Token, ()>,
+    answered: Notify,
+    shutting_down: AtomicBool,
 }
 
 impl AppState {
@@ -106,49 +139,148 @@ impl AppState {
             counter: Mutex::new(0),
             client_factory: Arc::new(ClientsPool::<GptClient>::new(factory)),
             config: config.clone(),
-            game_manager: GameManager::new()
+            game_manager: GameManager::new(),
+            prompt_guard: PromptGuard::new(config),
+            consistency: ConsistencyChecker::new(config),
+            fact_sheets: FactSheetStore::new(config),
+            answer_cache: AnswerCache::new(config),
+            rate_limiter: RateLimiter::new(config),
+            answering: DashMap::new(),
+            answered: Notify::new(),
+            shutting_down: AtomicBool::new(false),
+        }
+    }
+
+    /// Registers the question of the game as being answered until the guard drops.
+    fn begin_answer(self: &Arc<Self>, token: Token) -> impl Drop + Send + 'static {
+        self.answering.insert(token, ());
+        scopeguard::guard(self.clone(), move |state| {
+            state.answering.remove(&token);
+            state.answered.notify_waiters();
+        })
+    }
+
+    /// Refuses new questions and waits up to `shutdown_timeout_secs` for the
+    /// questions being answered; games still waiting get an error instead.
+    async fn drain(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+        let timeout = Duration::from_secs(self.config.www.shutdown_timeout_secs);
+        info!("waiting up to {:?} for {} questions being answered", timeout, self.answering.len());
+
+        let answered = async {
+            loop {
+                let notified = self.answered.notified();
+                if self.answering.is_empty() {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        if tokio::time::timeout(timeout, answered).await.is_ok() {
+            return;
+        }
+
+        let unanswered = self.answering.iter().map(|e| *e.key()).collect::<Vec<_>>();
+        warn!("{} questions not answered before shutdown", unanswered.len());
+        for token in unanswered {
+            let language = self.game_manager.get_language(&token).unwrap_or_default();
+            let _ = self.game_manager.handle_error_response(&token,
+                    GameError::GPTError(t(&language, "error.shutting_down")));
         }
     }
+
+    fn real_ip(&self, headers: &HeaderMap, addr: &SocketAddr) -> IpAddr {
+        get_real_ip(headers, addr.ip(), &self.config.www.trusted_proxies)
+    }
 }
 
 type Shared = Arc<AppState>;
 
 
 async fn log_requests(
+    State(state): State<Shared>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     req: Request,
     next: Next,
 ) -> Response {
-    let real_ip = get_real_ip(req.headers(), addr.ip());
+    let real_ip = state.real_ip(req.headers(), &addr);
     info!("{} {} {}", req.method(), req.uri().path_and_query().map(|x| x.as_str()).unwrap_or(""), real_ip);
     next.run(req).await
 }
 
-fn get_real_ip(headers: &HeaderMap, fallback_ip: std::net::IpAddr) -> std::net::IpAddr {
+/// Language used to localize error replies: the language of the game (or
+/// template, or spectated game) in the path of the legacy or v1 routes, the
+/// `lang` query parameter or `Accept-Language`.
+fn request_language_of(state: &AppState, req: &Request) -> Language {
+    let segments = req.uri().path().split('/').skip(1).take(4).collect::<Vec<_>>();
+    let token = |s: &str| Token::from_string(s).ok();
+    let game_language = |t: Token| state.game_manager.get_language(&t).ok();
+    let from_path = match segments.as_slice() {
+        ["api", "game", t, ..] | ["api", "v1", "games", t, ..] => token(t).and_then(game_language),
+        ["api", "template", t, ..] | ["api", "v1", "templates", t, ..] => token(t)
+            .and_then(|t| state.game_manager.get_game_template(&t).ok())
+            .map(|template| template.language),
+        ["api", "v1", "spectate", t, ..] => token(t)
+            .and_then(|t| state.game_manager.spectated_game(&t).ok())
+            .and_then(game_language),
+        _ => None,
+    };
+
+    from_path
+        .or_else(|| req.uri().query()?
+            .split('&')
+            .find_map(|kv| kv.strip_prefix("lang="))
+            .and_then(Language::from_str))
+        .or_else(|| req.headers().get(header::ACCEPT_LANGUAGE)?
+            .to_str().ok()
+            .and_then(parse_accept_language))
+        .unwrap_or_default()
+}
+
+async fn localize_errors(
+    State(state): State<Shared>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let lang = request_language_of(&state, &req);
+    with_request_language(lang, next.run(req)).await
+}
+
+/// Client address of the request. Forwarded headers are only believed when
+/// the peer is one of the trusted proxies; then the rightmost untrusted
+/// address of `X-Forwarded-For` is the client, so it can't be spoofed by
+/// prepending addresses.
+fn get_real_ip(headers: &HeaderMap, peer_ip: IpAddr, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer_ip) {
+        return peer_ip;
+    }
+
     // Try X-Forwarded-For header first
-    if let Some(xff) = headers.get("x-forwarded-for") {
-        if let Ok(xff_str) = xff.to_str() {
-            // X-Forwarded-For can be "client, proxy1, proxy2"
-            // We want the first (leftmost) IP which is the original client
-            if let Some(first_ip) = xff_str.split(',').next() {
-                if let Ok(ip) = first_ip.trim().parse() {
-                    return ip;
-                }
+    if let Some(xff) = headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
+        // X-Forwarded-For is "client, proxy1, proxy2"
+        let mut client = None;
+        for ip in xff.rsplit(',').map_while(|ip| ip.trim().parse::<IpAddr>().ok()) {
+            client = Some(ip);
+            if !trusted_proxies.contains(&ip) {
+                break;
             }
         }
+        if let Some(ip) = client {
+            return ip;
+        }
     }
-    
+
     // Try X-Real-IP header
     if let Some(real_ip) = headers.get("x-real-ip") {
         if let Ok(ip_str) = real_ip.to_str() {
-            if let Ok(ip) = ip_str.parse() {
+            if let Ok(ip) = ip_str.trim().parse() {
                 return ip;
             }
         }
     }
-    
+
     // Fallback to the direct connection IP
-    fallback_ip
+    peer_ip
 }
 
 fn logging() -> TraceLayer<SharedClassifier<ServerErrorsAsFailures>> {
@@ -170,6 +302,84 @@ fn logging() -> TraceLayer<SharedClassifier<ServerErrorsAsFailures>> {
         .on_failure(DefaultOnFailure::new().level(Level::ERROR))
 }
 
+fn spawn_periodic<F>(interval: Duration, task: F)
+where
+    F: Fn() + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            task();
+        }
+    });
+}
+
+/// Re-reads the API key files and the assets on SIGHUP.
+#[cfg(unix)]
+fn spawn_reload(config: Config) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+    use crate::key_ring::get_key_ring;
+    let mut hangup = signal(SignalKind::hangup()).context("SIGHUP handler not installed")?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP received, reloading API keys and assets");
+            if let Err(err) = get_key_ring().reload() {
+                warn!("API keys not reloaded: {}", err);
+            }
+            if let Err(err) = assets::reload(&config) {
+                warn!("assets not reloaded: {:#}", err);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Admin endpoints require `Authorization: Bearer <admin.token>`.
+fn check_admin(headers: &HeaderMap, config: &Config) -> Result<(), AppError> {
+    let Some(expected) = &config.admin.token else {
+        return Err(AppError::Unauthorized);
+    };
+    let provided = headers.get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if !provided.is_some_and(|provided| shared::token::same(provided.as_bytes(), expected.as_bytes())) {
+        return Err(AppError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Prometheus scrape endpoint; gauges are sampled on every scrape.
+async fn prometheus_metrics(State(state): State<Shared>) -> Response {
+    let pool = state.client_factory.stats();
+    let m = metrics();
+    m.pool_clients.set(pool.clients_total as i64);
+    m.pool_idle.set(pool.idle as i64);
+    m.pool_waiters.set(pool.waiters as i64);
+    m.answer_cache_entries.set(state.answer_cache.stats().entries as i64);
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], m.render()).into_response()
+}
+
+/// OpenAPI document of the `/api/v1` surface, served at `/api/v1/openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Guess Who API", version = "1"),
+    paths(v1_new_game, game, v1_ask, v1_transcript, v1_new_spectator, v1_spectate, v1_new_template, game_template,
+          v1_new_session, v1_session_games),
+    components(schemas(ErrorCode)),
+    tags(
+        (name = "games", description = "Playing a game"),
+        (name = "templates", description = "Custom games"),
+        (name = "sessions", description = "Player history"),
+    )
+)]
+struct ApiDoc;
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
 async fn redirect_to_game() -> Redirect {
     Redirect::to("/run/game")
 }
@@ -181,6 +391,37 @@ pub async fn run_server(
     let state = Shared::new(AppState::new(factory, config));
     tracing::info!("starting server on port {}", config.www.port);
 
+    if let Some(interval) = state.answer_cache.persist_interval() {
+        let state = state.clone();
+        spawn_periodic(interval, move || {
+            if let Err(err) = state.answer_cache.save() {
+                warn!("answer cache not saved: {}", err);
+            }
+        });
+    }
+
+    {
+        let state = state.clone();
+        spawn_periodic(Duration::from_secs(60), move || state.rate_limiter.prune());
+    }
+
+    if config.tokens.game_ttl_secs > 0 {
+        let state = state.clone();
+        let ttl = Duration::from_secs(config.tokens.game_ttl_secs);
+        spawn_periodic(Duration::from_secs(60), move || state.game_manager.delete_expired(ttl));
+    }
+
+    #[cfg(unix)]
+    spawn_reload(config.clone())?;
+
+    if let Some(interval) = usage::get_usage_tracker().persist_interval() {
+        spawn_periodic(interval, || {
+            if let Err(err) = usage::get_usage_tracker().save() {
+                warn!("usage ledger not saved: {}", err);
+            }
+        });
+    }
+
     let mut app = Router::new()
         .route("/api/token", get(index))
         .route("/api/game/new", get(new_game))
@@ -189,6 +430,26 @@ pub async fn run_server(
         .route("/api/game/{token}/ask", post(ask))
         .route("/api/game/{token}", get(game))
         .route("/run/game/{token}", get(game_by_template))
+        .route("/metrics", get(prometheus_metrics))
+        .route("/api/admin/usage", get(admin_usage))
+        .route("/api/admin/games", get(admin_games))
+        .route("/api/admin/games/{token}", get(admin_game).delete(admin_delete_game))
+        .route("/api/admin/games/{token}/end", post(admin_end_game))
+        .route("/api/admin/templates", get(admin_templates))
+        .route("/api/admin/assets", get(admin_assets))
+        .route("/api/admin/assets/reload", post(admin_reload_assets))
+        .route("/api/admin/variants", get(admin_variants))
+        .route("/api/v1/games", post(v1_new_game))
+        .route("/api/v1/games/{token}", get(game))
+        .route("/api/v1/games/{token}/questions", post(v1_ask))
+        .route("/api/v1/games/{token}/transcript", get(v1_transcript))
+        .route("/api/v1/games/{token}/spectators", post(v1_new_spectator))
+        .route("/api/v1/spectate/{token}", get(v1_spectate))
+        .route("/api/v1/sessions", post(v1_new_session))
+        .route("/api/v1/sessions/{token}/games", get(v1_session_games))
+        .route("/api/v1/templates", post(v1_new_template))
+        .route("/api/v1/templates/{token}", get(game_template))
+        .route("/api/v1/openapi.json", get(openapi))
         .route("/", get(redirect_to_game))
         .fallback(get(handler_404))
         ;
@@ -196,7 +457,7 @@ pub async fn run_server(
     let static_svc = ServiceBuilder::new()
         .layer(logging())
         .service(
-            ServeDir::new(&config.dirs.get_path(DirType::Www))
+            ServeDir::new(config.dirs.get_path(DirType::Www))
                 .append_index_html_on_directories(true)
                 .precompressed_br()
                 .precompressed_gzip(),
@@ -206,12 +467,12 @@ pub async fn run_server(
     let static_svc = ServiceBuilder::new()
         .layer(logging())
         .service(
-            ServeDir::new(&config.dirs.get_path(DirType::Dist))
+            ServeDir::new(config.dirs.get_path(DirType::Dist))
                 .append_index_html_on_directories(true)
                 .precompressed_br()
                 .precompressed_gzip()
                 .not_found_service(ServeFile::new(PathBuf::new()
-                    .join(&config.dirs.get_path(DirType::Dist)).join("index.html")))
+                    .join(config.dirs.get_path(DirType::Dist)).join("index.html")))
         );
 
     app = app.nest_service("/run", static_svc);
@@ -220,67 +481,254 @@ pub async fn run_server(
 
     let app = app
         .fallback(handler_404)
-        .with_state(state)
-        .layer(middleware::from_fn(log_requests))
+        .with_state(state.clone())
+        .layer(middleware::from_fn_with_state(state.clone(), localize_errors))
+        .layer(middleware::from_fn_with_state(state.clone(), log_requests))
         .layer(logging());
+    let persisted = state.clone();
+
+    let tls = match config.get_tls_files() {
+        Some((cert, key)) => Some(load_tls_config(&cert, &key)?),
+        None => None,
+    };
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], config.www.port));
-    let listener = TcpListener::bind(addr).await.context("Failed to bind to address")?;
+    let (stop, stopped) = watch::channel(false);
+    let mut servers = JoinSet::new();
+    for addr in config.get_listen_addrs() {
+        let listener = TcpListener::bind(addr).await
+            .with_context(|| format!("Failed to bind to {}", addr))?;
+        let app = app.clone().into_make_service_with_connect_info::<SocketAddr>();
+        let mut stopped = stopped.clone();
+        let shutdown = async move {
+            let _ = stopped.wait_for(|stop| *stop).await;
+        };
+        match &tls {
+            Some(tls) => {
+                // axum derives `ConnectInfo` only for its TcpListener and for `TapIo`
+                // listeners (from their `Addr`, the TCP peer here); the no-op tap is
+                // what makes `ConnectInfo<SocketAddr>` available behind TLS
+                let listener = TlsListener::new(listener, tls.clone())?.tap_io(|_| ());
+                servers.spawn(async move { axum::serve(listener, app).with_graceful_shutdown(shutdown).await });
+            }
+            None => {
+                servers.spawn(async move { axum::serve(listener, app).with_graceful_shutdown(shutdown).await });
+            }
+        }
+        info!("server bound to {}{} - ready to accept connections", addr, if tls.is_some() { " (TLS)" } else { "" });
+    }
 
-    info!("server bound to {} - ready to accept connections", addr);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        state.drain().await;
+        let _ = stop.send(true);
+    });
 
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await
-    .context("Server error")?;
+    while let Some(result) = servers.join_next().await {
+        result.context("Server task failed")?.context("Server error")?;
+    }
 
     info!("server shutting down");
+    if let Err(err) = persisted.answer_cache.save() {
+        warn!("answer cache not saved: {}", err);
+    }
+    if let Err(err) = usage::get_usage_tracker().save() {
+        warn!("usage ledger not saved: {}", err);
+    }
     Ok(())
 }
 
-async fn handler_404() -> impl IntoResponse {
+/// Resolves on SIGINT or SIGTERM.
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!("SIGINT handler not installed: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                warn!("SIGTERM handler not installed: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => info!("SIGINT received"),
+        _ = terminate => info!("SIGTERM received"),
+    }
+}
+
+async fn handler_404() -> AppError {
     info!("404 - route not found");
-    (StatusCode::NOT_FOUND, "Not found")
+    AppError::NotFound
 }
 
 
+/// Game state; with `wait` it waits a while for the pending answer.
+#[utoipa::path(
+    get,
+    path = "/api/v1/games/{token}",
+    params(
+        ("token" = String, Path, description = "Game token"),
+        ("wait" = Option<bool>, Query, description = "Wait a few seconds for the pending answer"),
+        ("quiet" = Option<bool>, Query, description = "Omit the content while the answer is pending"),
+    ),
+    responses(
+        (status = 200, description = "Game state, `pending` while the answer isn't ready", body = ServerResponse<GameState>),
+        (status = 400, description = "Malformed token or one of another kind", body = ErrorResponse),
+        (status = 403, description = "A spectator token", body = ErrorResponse),
+        (status = 404, description = "Game not found", body = ErrorResponse),
+    ),
+    tag = "games"
+)]
 async fn game(
     headers: HeaderMap,
     State(state): State<Shared>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Path(token_str): Path<String>,
+    GameToken(token): GameToken,
     Query(query): Query<WaitParam>
-) -> Result<String, AppError> {
-    let real_ip = get_real_ip(&headers, addr.ip());
+) -> Result<Json<ServerResponse<GameState>>, AppError> {
+    let real_ip = state.real_ip(&headers, &addr);
     info!("game request from {}", real_ip);
     query.check()?;
-    let token = Token::from_string(token_str.as_str()).map_err(|e| {
-        warn!("invalid token from {}: {} - {}", real_ip, token_str, e);
-        e
-    })?;
     let pending = state.game_manager.is_pending(&token)?;
 
     if pending && query.wait == 1 {
         info!("client {} waiting for answer", real_ip);
         let res = state.game_manager.wait_for_answer(&token, Duration::new(5, 0)).await;
-        if res.is_err() {
-            info!("answer not ready for {}, reason={}", real_ip, res.as_ref().unwrap_err());
-            res?;
+        if let Err(err) = res {
+            info!("answer not ready for {}, reason={}", real_ip, err);
+            metrics().long_poll.with_label_values(&["timeout"]).inc();
+            return Err(err);
         }
+        metrics().long_poll.with_label_values(&["answered"]).inc();
         info!("answer ready for {}", real_ip);
     }
 
     let status = if pending {Status::Pending} else {Status::Ok};
 
     if query.quiet == 1 && status == Status::Pending {
-        let game_state = ServerResponse::<()>::from_status(status);
-        return Ok(game_state.to_response()?);
+        return Ok(Json(ServerResponse::from_status(status)));
     }
 
     let game_state = state.game_manager.get_game_state(&token)?;
-    Ok(ServerResponse::from_content(status, game_state).to_response()?)
+    Ok(Json(ServerResponse::from_content(status, game_state)))
+}
+
+/// Creates the read-only token of the game; all spectators share it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/{token}/spectators",
+    params(("token" = String, Path, description = "Game token")),
+    responses(
+        (status = 200, description = "Spectator token", body = TokenResponse),
+        (status = 400, description = "Malformed token or one of another kind", body = ErrorResponse),
+        (status = 403, description = "A spectator token", body = ErrorResponse),
+        (status = 404, description = "Game not found", body = ErrorResponse),
+    ),
+    tag = "games"
+)]
+async fn v1_new_spectator(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    GameToken(token): GameToken,
+) -> Result<Json<TokenResponse>, AppError> {
+    let real_ip = state.real_ip(&headers, &addr);
+    let spectator = state.game_manager.spectator_token(&token)?;
+    info!("spectator token of game {} for {}", token, real_ip);
+    Ok(Json(TokenResponse { token: spectator.to_string() }))
+}
+
+/// Game state for spectators; comments stay hidden as for the player.
+#[utoipa::path(
+    get,
+    path = "/api/v1/spectate/{token}",
+    params(
+        ("token" = String, Path, description = "Spectator token"),
+        ("wait" = Option<bool>, Query, description = "Wait a few seconds for the next change of the game"),
+    ),
+    responses(
+        (status = 200, description = "Game state, `pending` while a question is being answered", body = ServerResponse<GameState>),
+        (status = 400, description = "Malformed token or one of another kind", body = ErrorResponse),
+        (status = 404, description = "Game not found", body = ErrorResponse),
+    ),
+    tag = "games"
+)]
+async fn v1_spectate(
+    State(state): State<Shared>,
+    SpectatorToken(spectator): SpectatorToken,
+    Query(query): Query<WaitParam>
+) -> Result<Json<ServerResponse<GameState>>, AppError> {
+    query.check()?;
+    let token = state.game_manager.spectated_game(&spectator)?;
+
+    if query.wait == 1 && state.game_manager.is_game_active(&token)? {
+        state.game_manager.wait_for_change(&token, Duration::new(5, 0)).await?;
+    }
+
+    let status = if state.game_manager.is_pending(&token)? {Status::Pending} else {Status::Ok};
+    let game_state = state.game_manager.get_game_state(&token)?;
+    Ok(Json(ServerResponse::from_content(status, game_state)))
+}
+
+#[derive(Deserialize)]
+struct TranscriptParam {
+    #[serde(default)]
+    format: TranscriptFormat,
+}
+
+/// Exports the finished game, as the player sees it.
+#[utoipa::path(
+    get,
+    path = "/api/v1/games/{token}/transcript",
+    params(
+        ("token" = String, Path, description = "Game token"),
+        ("format" = Option<String>, Query, description = "`json` (default), `md` or `html`"),
+    ),
+    responses(
+        (status = 200, description = "The transcript; JSON, Markdown or a standalone HTML page", body = Transcript),
+        (status = 400, description = "Malformed token or one of another kind", body = ErrorResponse),
+        (status = 403, description = "A spectator token", body = ErrorResponse),
+        (status = 404, description = "Game not found", body = ErrorResponse),
+        (status = 409, description = "The game isn't over", body = ErrorResponse),
+    ),
+    tag = "games"
+)]
+async fn v1_transcript(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    GameToken(token): GameToken,
+    Query(query): Query<TranscriptParam>,
+) -> Result<Response, AppError> {
+    let real_ip = state.real_ip(&headers, &addr);
+    let game_state = state.game_manager.get_game_state(&token)?;
+    if !game_state.game_ended {
+        return Err(AppError::GameInProgress);
+    }
+
+    let transcript = Transcript::from_game(&game_state);
+    let body = match query.format {
+        TranscriptFormat::Json => serde_json::to_string_pretty(&transcript)?,
+        TranscriptFormat::Md => transcript::to_markdown(&transcript),
+        TranscriptFormat::Html => transcript::to_html(&transcript),
+    };
+    info!("transcript of {} exported as {} for {}", token, query.format.extension(), real_ip);
+    let file_name = format!("attachment; filename=\"gggame-{}.{}\"", token, query.format.extension());
+    Ok((
+        [(header::CONTENT_TYPE, query.format.content_type().to_string()), (header::CONTENT_DISPOSITION, file_name)],
+        body,
+    ).into_response())
 }
 
 
@@ -299,166 +747,526 @@ fn is_give_up(s: &str) -> bool {
     matches!(n.as_str(), "IM LOSER" | "I AM LOSER" | "IM A LOSER" | "KONEC" | "konec")
 }
 
+/// Legacy route taking the question as a raw body.
 async fn ask(
     headers: HeaderMap,
     State(state): State<Shared>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Path(token_str): Path<String>,
+    GameToken(token): GameToken,
     body: Bytes,
 ) -> Result<String, AppError> {
-    let real_ip = get_real_ip(&headers, addr.ip());
-    let token = Token::from_string(token_str.as_str()).map_err(|e| {
-        warn!("invalid token from {}: {} - {}", real_ip, token_str, e);
-        e
-    })?;
+    let real_ip = state.real_ip(&headers, &addr);
+    ask_question(state, real_ip, token, String::from_utf8_lossy(&body).as_ref())?;
+    Ok(status_response(Status::Ok))
+}
+
+/// Asks a question; the answer arrives in the game state.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games/{token}/questions",
+    params(("token" = String, Path, description = "Game token")),
+    request_body = AskRequest,
+    responses(
+        (status = 200, description = "Question accepted, or `pending` if another one is being answered", body = StatusResponse),
+        (status = 400, description = "Malformed token, a token of another kind, or an invalid question", body = ErrorResponse),
+        (status = 403, description = "A spectator token", body = ErrorResponse),
+        (status = 404, description = "Game not found", body = ErrorResponse),
+        (status = 409, description = "The game is over", body = ErrorResponse),
+        (status = 429, description = "Too many questions", body = ErrorResponse),
+        (status = 503, description = "Spending limit reached", body = ErrorResponse),
+    ),
+    tag = "games"
+)]
+async fn v1_ask(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    GameToken(token): GameToken,
+    body: Bytes,
+) -> Result<Json<StatusResponse>, AppError> {
+    let real_ip = state.real_ip(&headers, &addr);
+    let request = serde_json::from_slice::<AskRequest>(&body).map_err(AppError::InvalidJson)?;
+    ask_question(state, real_ip, token, &request.question)?;
+    Ok(Json(StatusResponse { status: Status::Ok }))
+}
+
+fn ask_question(state: Shared, real_ip: IpAddr, token: Token, question: &str) -> Result<(), AppError> {
+    if state.shutting_down.load(Ordering::Relaxed) {
+        info!("refusing question from {}, shutting down", real_ip);
+        return Err(AppError::ServerBusy);
+    }
 
     if !state.game_manager.is_game_active(&token)? {
         warn!("asking in inactive game {} {}", token.to_string(), real_ip);
         return Err(AppError::InactiveGame);
     }
 
-    let Some(question) = sanitize_question(&String::from_utf8_lossy(&body).to_string()) else {
+    let Some(question) = sanitize_question(question) else {
         info!("invalid question from {}", real_ip);
-        return Err(AppError::InvalidToken);
+        return Err(AppError::InvalidInput);
     };
 
     info!("question from {}: \"{}\"", real_ip, question);
 
-    let mut gpt_client = state.client_factory.pop();
-    gpt_client.update().await.unwrap();
+    if let Err(period) = usage::get_usage_tracker().check_budget() {
+        warn!("{:?} budget exhausted, refusing question from {}", period, real_ip);
+        return Err(AppError::BudgetExceeded(period));
+    }
 
+    state.rate_limiter.check_question(&token)?;
 
     let language = state.game_manager.get_language(&token)?;
+    let target = state.game_manager.get_target(&token)?;
+    let variant = state.game_manager.get_variant(&token)?;
 
     let question_builder = GameStepBuilder::new(&state.config)
-        .set_target(&state.game_manager.get_target(&token)?)
+        .set_assets(state.game_manager.get_assets(&token)?)
+        .set_variant(variant.clone())
+        .set_model_profile(state.game_manager.get_model_profile(&token)?)
+        .set_difficulty(state.game_manager.get_difficulty(&token)?)
+        .set_custom_info(state.game_manager.get_custom_info(&token)?)
+        .set_target(&target)
         .set_language(&language)
         .set_question(&question)
+        .set_fact_sheet(state.fact_sheets.get(&language, &target))
+        .set_usage_tag(UsageTag::for_game(Purpose::Answer, &token, state.game_manager.get_template(&token))
+            .with_variant(variant))
         .create()?
     ;
 
     state.game_manager.set_pending_question(&token, &question_builder.get_original_question())?;
+    metrics().questions.with_label_values(&[language.to_code()]).inc();
+
+    let answering = state.begin_answer(token);
 
     // !!! ASK !!!
     tokio::spawn(async move {
+        let _answering = answering;
 
         if is_give_up(&question) {
             info!("giving up {}: \"{}\"", real_ip, question);
+            metrics().give_ups.inc();
 
             let template = t(&language, "game.final_answer");
             let final_message = template.replace("{}", &question_builder.get_target());
             let answer = shared::messages::Answer::get_final_answer(&final_message);
+            let _ = state.game_manager.give_up(&token, &answer);
+            return
+        }
+
+        let guard_verdict = state.prompt_guard.check(&language, &question);
+        if guard_verdict.is_rejected() {
+            info!("question rejected by guard {} score={}: \"{}\"", real_ip, guard_verdict.score, question);
+            let answer = shared::messages::Answer::get_behave_answer(&t(&language, "game.behave"));
             let _ = state.game_manager.answer_pending_question(&token, &answer);
-            let _ = state.game_manager.finish_game(&token);
             return
         }
+        if guard_verdict.is_flagged() {
+            state.game_manager.flag_pending_question(&token);
+        }
+
+        let cache_key = CacheKey::new(&question_builder.get_target(), &language, &question,
+                                      &question_builder.get_prompt_version(&state.config),
+                                      &question_builder.get_answer_model(&state.config).model,
+                                      question_builder.get_model_profile());
+        if let Some(answer) = state.answer_cache.get(&cache_key) {
+            info!("cached answer for {}: \"{}\" -> {:?}", real_ip, question, answer.verdict);
+            let _ = state.game_manager.answer_pending_question(&token, &answer);
+            return
+        }
+
+        let Ok(deleted) = state.game_manager.deleted(&token) else {
+            return
+        };
+        let gpt_client = match state.client_factory.acquire(deleted).await {
+            Ok(gpt_client) => gpt_client,
+            Err(AppError::GameNotFound) => {
+                info!("game {} deleted while waiting for a client", token);
+                return
+            }
+            Err(err) => {
+                info!("no client for {}: {}", real_ip, err);
+                let _ = state.game_manager.handle_error_response(&token,
+                       GameError::GPTError(t(&language, "error.server_busy")));
+                return
+            }
+        };
 
         info!("sending question to GPT for {}: \"{}\"", real_ip, question);
-        let result = gpt_client.client().ask(&question_builder.build_question(),
-                                             &question_builder.build_params(&state.config)).await;
+        let result = gpt_client.client().ask_with_fallback(&question_builder.build_question(),
+                                                           &question_builder.build_params(&state.config),
+                                                           is_valid_reply).await;
 
         match result {
             Ok(gpt_answer) => {
-                let s = gpt_answer.to_string().unwrap_or("UNABLE; this is weird".to_string());
-                info!("GPT response received {} [{}]", real_ip, &s);
+                let mut answer = match gpt_answer.to_string() {
+                    Some(s) => {
+                        info!("GPT response received {} [{}]", real_ip, &s);
+                        question_builder.parse_answer(&s)
+                    }
+                    None => {
+                        info!("GPT response without text for {}", real_ip);
+                        shared::messages::Answer::from_verdict(Verdict::Unable, &t(&language, "game.gpt_fallback"))
+                    }
+                };
+                let mut conflicts = vec![];
+
+                if state.consistency.is_enabled() && answer.verdict != Some(Verdict::Final)
+                    && state.game_manager.take_consistency_check(&token, state.consistency.max_checks_per_game()).unwrap_or(false) {
+                    let records = state.game_manager.get_records(&token).unwrap_or_default();
+                    (answer, conflicts) = state.consistency.verify(
+                        gpt_client.client(), &state.config, &question_builder, records, answer).await;
+                }
 
-                let answer = shared::messages::Answer::parse_from_string(&s);
-                let _ = state.game_manager.answer_pending_question(&token, &answer);
+                if conflicts.is_empty() {
+                    state.answer_cache.insert(cache_key, &answer);
+                }
+                if answer.verdict == Some(Verdict::Final) {
+                    metrics().solves.inc();
+                }
+                let _ = state.game_manager.answer_pending_question_flagged(&token, &answer, &conflicts);
             }
             Err(err) => {
                 info!("GPT response ERROR for {}: {}", real_ip, err);
-                let _ = state.game_manager.handle_error_response(&token,
-                       GameError::GPTError(err.to_string()));
+                let message = match err.downcast_ref::<AppError>() {
+                    Some(AppError::RateLimited(retry_after)) =>
+                        tf(&language, "error.rate_limited", &[&retry_after.to_string()]),
+                    _ => err.to_string(),
+                };
+                let _ = state.game_manager.handle_error_response(&token, GameError::GPTError(message));
             }
         }
     });
 
     // -----------
-    Ok(status_response(Status::Ok))
+    Ok(())
 }
 
 async fn index(
     headers: HeaderMap,
-    State(_state): State<Shared>,
+    State(state): State<Shared>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>
 ) -> String {
-    let real_ip = get_real_ip(&headers, addr.ip());
+    let real_ip = state.real_ip(&headers, &addr);
     let token = Token::new(TokenType::Answer).to_string();
     info!("token request from {}: {}", real_ip, token);
     token
 }
 
 
+/// Legacy route returning the bare token.
 async fn new_game(
     headers: HeaderMap,
     State(state): State<Shared>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Query(game_params): Query<NewGameParam>
 ) -> Result<String, AppError> {
-    let real_ip = get_real_ip(&headers, addr.ip());
-    let identity = crate::locale::get_random_identity(&game_params.get_language()).ok_or(AppError::InternalServerError)?;
-    let game_token =
-        state.game_manager.new_game(&identity, game_params.get_language(), None).to_string();
+    let real_ip = state.real_ip(&headers, &addr);
+    Ok(create_game(&state, real_ip, game_params.get_language(), game_params.get_difficulty())?.to_string())
+}
+
+/// Starts a new game with a random identity.
+#[utoipa::path(
+    post,
+    path = "/api/v1/games",
+    request_body = NewGameRequest,
+    responses(
+        (status = 200, description = "Game created", body = TokenResponse),
+        (status = 400, description = "Malformed session token", body = ErrorResponse),
+        (status = 429, description = "Too many games created from the address", body = ErrorResponse),
+    ),
+    tag = "games"
+)]
+async fn v1_new_game(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    body: Bytes,
+) -> Result<Json<TokenResponse>, AppError> {
+    let real_ip = state.real_ip(&headers, &addr);
+    let request = if body.is_empty() {
+        NewGameRequest::default()
+    } else {
+        serde_json::from_slice::<NewGameRequest>(&body).map_err(AppError::InvalidJson)?
+    };
+    let session = request.session.as_deref()
+        .map(|session| parse_token(session, TokenType::Session))
+        .transpose()?;
+    let token = create_game(&state, real_ip, request.lang.unwrap_or_default(), request.difficulty.unwrap_or_default())?;
+    if let Some(session) = session {
+        state.game_manager.assign_session(&token, &session);
+    }
+    Ok(Json(TokenResponse { token: token.to_string() }))
+}
+
+/// Starts an anonymous player session; games created with it make up the
+/// player's history.
+#[utoipa::path(
+    post,
+    path = "/api/v1/sessions",
+    responses(
+        (status = 200, description = "Session token", body = TokenResponse),
+        (status = 429, description = "Too many sessions or games created from the address", body = ErrorResponse),
+    ),
+    tag = "sessions"
+)]
+async fn v1_new_session(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<TokenResponse>, AppError> {
+    let real_ip = state.real_ip(&headers, &addr);
+    state.rate_limiter.check_new_game(real_ip)?;
+    let session = Token::new(TokenType::Session);
+    info!("new session for {}: {}", real_ip, session);
+    Ok(Json(TokenResponse { token: session.to_string() }))
+}
+
+/// Games of the session still on the server, the newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sessions/{token}/games",
+    params(("token" = String, Path, description = "Session token")),
+    responses(
+        (status = 200, description = "The games", body = ServerResponse<Vec<HistoryGame>>),
+        (status = 400, description = "Malformed token or one of another kind", body = ErrorResponse),
+    ),
+    tag = "sessions"
+)]
+async fn v1_session_games(
+    State(state): State<Shared>,
+    SessionToken(session): SessionToken,
+) -> Json<ServerResponse<Vec<HistoryGame>>> {
+    Json(ServerResponse::from_content(Status::Ok, state.game_manager.history(&session)))
+}
+
+fn create_game(state: &Shared, real_ip: IpAddr, language: Language, difficulty: Difficulty) -> Result<Token, AppError> {
+    state.rate_limiter.check_new_game(real_ip)?;
+    let assets = assets::current();
+    let identity = assets.locale.get_random_identity(&language, difficulty).ok_or(AppError::InternalServerError)?;
+    let question_limit = state.config.difficulty.question_limit(difficulty);
+    let game_token = state.game_manager.new_game(&identity, language.clone(), None, difficulty, question_limit, assets);
     info!("new-game-created-for {}: {}", real_ip, game_token);
+    prepare_fact_sheet(state, language, identity);
     Ok(game_token)
 }
 
+/// Generates the fact sheet of the identity in the background, so it's
+/// usually ready when the first question arrives.
+fn prepare_fact_sheet(state: &Shared, language: Language, identity: String) {
+    if !state.fact_sheets.is_enabled() || !state.fact_sheets.begin_generate(&language, &identity) {
+        return;
+    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        let gpt_client = match state.client_factory.acquire(std::future::pending()).await {
+            Ok(gpt_client) => gpt_client,
+            Err(err) => {
+                warn!("fact sheet for [{}] not generated: {}", identity, err);
+                state.fact_sheets.cancel_generate(&language, &identity);
+                return;
+            }
+        };
+        state.fact_sheets.generate(gpt_client.client(), &language, &identity).await;
+    });
+}
+
+/// Legacy route returning the bare template token.
 async fn new_game_template(
     headers: HeaderMap,
     State(state): State<Shared>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     body: Bytes,
 ) -> Result<String, AppError> {
-    let real_ip = get_real_ip(&headers, addr.ip());
+    let real_ip = state.real_ip(&headers, &addr);
+    Ok(create_template(&state, real_ip, &body)?.to_string())
+}
+
+/// Defines a custom game others can play.
+#[utoipa::path(
+    post,
+    path = "/api/v1/templates",
+    request_body = GameTemplate,
+    responses(
+        (status = 200, description = "Template created", body = TokenResponse),
+        (status = 400, description = "Malformed template", body = ErrorResponse),
+        (status = 422, description = "Invalid identity", body = ErrorResponse),
+        (status = 429, description = "Too many games created from the address", body = ErrorResponse),
+    ),
+    tag = "templates"
+)]
+async fn v1_new_template(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    body: Bytes,
+) -> Result<Json<TokenResponse>, AppError> {
+    let real_ip = state.real_ip(&headers, &addr);
+    let token = create_template(&state, real_ip, &body)?;
+    Ok(Json(TokenResponse { token: token.to_string() }))
+}
 
-    let body = String::from_utf8_lossy(&body).to_string();
-    let template = serde_json::from_str::<GameTemplate>(&body)?;
+fn create_template(state: &Shared, real_ip: IpAddr, body: &[u8]) -> Result<Token, AppError> {
+    state.rate_limiter.check_new_game(real_ip)?;
+    let template = serde_json::from_slice::<GameTemplate>(body).map_err(AppError::InvalidJson)?;
 
     let check_result = template.check();
     if check_result != GameTemplateStatus::Ok {
         return Err(AppError::InvalidGameTemplate(check_result));
     }
+    if template.model_profile.as_ref().is_some_and(|p| !state.config.answer.has_profile(p)) {
+        return Err(AppError::InvalidGameTemplate(GameTemplateStatus::UnknownModelProfile));
+    }
 
     let template_token = state.game_manager.define_game_template(&template)?;
-    let template_token = template_token.to_string();
     info!("new-game-template-created-for {}; template_token={}", real_ip, template_token);
     Ok(template_token)
 }
 
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/templates/{token}",
+    params(("token" = String, Path, description = "Template token")),
+    responses(
+        (status = 200, description = "The template", body = ServerResponse<GameTemplate>),
+        (status = 400, description = "Malformed token or one of another kind", body = ErrorResponse),
+        (status = 404, description = "Template not found", body = ErrorResponse),
+    ),
+    tag = "templates"
+)]
 async fn game_template(
-    headers: HeaderMap,
     State(state): State<Shared>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Path(token_str): Path<String>,
-) -> Result<String, AppError> {
-    let real_ip = get_real_ip(&headers, addr.ip());
-    let token = Token::from_string(token_str.as_str()).map_err(|e| {
-        warn!("invalid token from {}: {} - {}", real_ip, token_str, e);
-        e
-    })?;
+    TemplateToken(token): TemplateToken,
+) -> Result<Json<ServerResponse<GameTemplate>>, AppError> {
     let game_template = state.game_manager.get_game_template(&token)?;
-    Ok(ServerResponse::from_content(Status::Ok, game_template).to_response()?)
+    Ok(Json(ServerResponse::from_content(Status::Ok, game_template)))
 }
 
 
 async fn game_by_template(
-    headers: HeaderMap,
     State(state): State<Shared>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(token_str): Path<String>,
 ) -> Redirect {
     let default_redir = Redirect::to("/run/game");
-    let real_ip = get_real_ip(&headers, addr.ip());
-    let Ok(token) = Token::from_string(token_str.as_str()).map_err(|e| {
-        warn!("invalid token from {}: {} - {}", real_ip, token_str, e);
-        e
-    }) else {
+    let Ok(token) = parse_token(&token_str, TokenType::GameTemplate) else {
         return default_redir;
     };
-    let Ok(game_template) = state.game_manager.get_game_template(&token) else {
+    let Ok(_game_template) = state.game_manager.get_game_template(&token) else {
         return default_redir;
     };
 
     Redirect::to("/run/game")
 }
+
+fn admin_request(state: &AppState, headers: &HeaderMap, addr: &SocketAddr) -> Result<IpAddr, AppError> {
+    let real_ip = state.real_ip(headers, addr);
+    check_admin(headers, &state.config).inspect_err(|_| {
+        warn!("unauthorized admin request from {}", real_ip);
+    })?;
+    Ok(real_ip)
+}
+
+async fn admin_usage(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<ServerResponse<UsageReport>>, AppError> {
+    admin_request(&state, &headers, &addr)?;
+    let report = usage::get_usage_tracker().report();
+    Ok(Json(ServerResponse::from_content(Status::Ok, report)))
+}
+
+async fn admin_games(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<ServerResponse<Vec<AdminGameSummary>>>, AppError> {
+    admin_request(&state, &headers, &addr)?;
+    Ok(Json(ServerResponse::from_content(Status::Ok, state.game_manager.list_games())))
+}
+
+async fn admin_game(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    GameToken(token): GameToken,
+) -> Result<Json<ServerResponse<AdminGameDetail>>, AppError> {
+    admin_request(&state, &headers, &addr)?;
+    Ok(Json(ServerResponse::from_content(Status::Ok, state.game_manager.inspect_game(&token)?)))
+}
+
+async fn admin_end_game(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    GameToken(token): GameToken,
+) -> Result<Json<StatusResponse>, AppError> {
+    let real_ip = admin_request(&state, &headers, &addr)?;
+    state.game_manager.force_end_game(&token)?;
+    info!("admin {} ended game {}", real_ip, token);
+    Ok(Json(StatusResponse { status: Status::Ok }))
+}
+
+async fn admin_delete_game(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    GameToken(token): GameToken,
+) -> Result<Json<StatusResponse>, AppError> {
+    let real_ip = admin_request(&state, &headers, &addr)?;
+    state.game_manager.delete_game(&token)?;
+    info!("admin {} deleted game {}", real_ip, token);
+    Ok(Json(StatusResponse { status: Status::Ok }))
+}
+
+async fn admin_templates(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<ServerResponse<Vec<AdminTemplate>>>, AppError> {
+    admin_request(&state, &headers, &addr)?;
+    Ok(Json(ServerResponse::from_content(Status::Ok, state.game_manager.list_templates())))
+}
+
+fn assets_report(assets: &Assets) -> AdminAssets {
+    AdminAssets {
+        version: assets.version.clone(),
+        identities: assets.locale.available_languages().into_iter()
+            .map(|lang| (lang.clone(), assets.identities_count(&lang)))
+            .collect(),
+    }
+}
+
+async fn admin_assets(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<ServerResponse<AdminAssets>>, AppError> {
+    admin_request(&state, &headers, &addr)?;
+    Ok(Json(ServerResponse::from_content(Status::Ok, assets_report(&assets::current()))))
+}
+
+/// Swaps in new instructions, identities and translations for new games.
+async fn admin_reload_assets(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<ServerResponse<AdminAssets>>, AppError> {
+    let real_ip = admin_request(&state, &headers, &addr)?;
+    let assets = assets::reload(&state.config).inspect_err(|err| {
+        warn!("assets not reloaded by admin {}: {:#}", real_ip, err);
+    })?;
+    info!("admin {} reloaded assets, version {}", real_ip, assets.version);
+    Ok(Json(ServerResponse::from_content(Status::Ok, assets_report(&assets))))
+}
+
+/// Outcomes of the prompt variants, to compare instruction changes.
+async fn admin_variants(
+    headers: HeaderMap,
+    State(state): State<Shared>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+) -> Result<Json<ServerResponse<Vec<AdminVariantStats>>>, AppError> {
+    admin_request(&state, &headers, &addr)?;
+    Ok(Json(ServerResponse::from_content(Status::Ok, experiments().report(&assets::current()))))
+}
@@ -0,0 +1,34 @@
+/// Lowercases the text, drops punctuation and collapses whitespace, so
+/// "Ignore, previous   INSTRUCTIONS!" matches "ignore previous instructions".
+pub fn normalize(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .flat_map(|c| c.to_lowercase())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_drops_punctuation_and_case() {
+        assert_eq!(normalize("Ignore, previous   INSTRUCTIONS!"), "ignore previous instructions");
+        assert_eq!(normalize("  Are you\talive?\n"), "are you alive");
+    }
+
+    #[test]
+    fn normalize_keeps_letters_of_any_script() {
+        assert_eq!(normalize("Jsi ŽENA?"), "jsi žena");
+        assert_eq!(normalize("Born in 1879?"), "born in 1879");
+    }
+
+    #[test]
+    fn normalize_of_symbols_only_is_empty() {
+        assert_eq!(normalize("?!... ;"), "");
+        assert_eq!(normalize(""), "");
+    }
+}
@@ -1,24 +1,75 @@
-use rand::Rng;
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::sign::Signer;
 use shared::token::*;
+use time::OffsetDateTime;
+use tracing::warn;
+
+use crate::config::Config;
 
 pub trait TokenGen {
     fn new(token_type: TokenType) -> Self;
 }
 
-fn random_bytes(leading_letter: u8) -> [u8; TOKEN_LENGTH] {
-    const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
-    let mut rng = rand::rng();
-    let mut buf = [0u8; TOKEN_LENGTH];
-    buf[0] = leading_letter;
-    for b in &mut buf[1..] {
-        *b = CHARSET[rng.random_range(0..CHARSET.len())];
+/// HMAC-SHA256 of the tokens, truncated.
+struct HmacSigner {
+    key: PKey<Private>,
+}
+
+impl TokenSigner for HmacSigner {
+    fn sign(&self, payload: &[u8]) -> [u8; SIGNATURE_BYTES] {
+        let mut signer = Signer::new(MessageDigest::sha256(), &self.key)
+            .expect("HMAC-SHA256 not available");
+        let mac = signer.sign_oneshot_to_vec(payload)
+            .expect("HMAC-SHA256 failed");
+        let mut signature = [0u8; SIGNATURE_BYTES];
+        signature.copy_from_slice(&mac[..SIGNATURE_BYTES]);
+        signature
+    }
+}
+
+/// Lifetime of the game and spectator tokens, if they expire.
+static GAME_TTL: OnceLock<Option<i64>> = OnceLock::new();
+
+/// Installs the signer of the tokens, keyed by `tokens.secret`.
+pub fn init_tokens(config: &Config) -> Result<()> {
+    let secret = match &config.tokens.secret {
+        Some(secret) => secret.as_bytes().to_vec(),
+        None => {
+            warn!("tokens.secret not set, tokens won't survive a restart");
+            let mut secret = vec![0u8; 32];
+            openssl::rand::rand_bytes(&mut secret)?;
+            secret
+        }
+    };
+    install_signer(Box::new(HmacSigner { key: PKey::hmac(&secret)? }));
+    let ttl = config.tokens.game_ttl_secs;
+    let _ = GAME_TTL.set((ttl > 0).then_some(ttl as i64));
+    Ok(())
+}
+
+/// Fixed key signer without expiry, for the tests of modules handling tokens.
+#[cfg(test)]
+pub fn init_test_tokens() {
+    install_signer(Box::new(HmacSigner { key: PKey::hmac(b"test secret of the tokens").unwrap() }));
+}
+
+fn expiry(token_type: TokenType) -> Option<i64> {
+    match token_type {
+        TokenType::Game | TokenType::Spectator => GAME_TTL.get().copied().flatten()
+            .map(|ttl| OffsetDateTime::now_utc().unix_timestamp() + ttl),
+        TokenType::Answer | TokenType::GameTemplate | TokenType::Session => None,
     }
-    buf
 }
+
 impl TokenGen for Token {
     fn new(token_type: TokenType) -> Self {
-        Self {
-            token: random_bytes(token_type.leading_byte())
-        }
+        let mut entropy = [0u8; ENTROPY_BYTES];
+        openssl::rand::rand_bytes(&mut entropy).expect("CSPRNG failed");
+        Token::sign(token_type, &entropy, expiry(token_type))
+            .expect("Tokens not initialized. Call init_tokens() first.")
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,176 @@
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use shared::locale::Language;
+use shared::messages::{Record, Transcript, Verdict};
+
+use crate::locale::{t, tf};
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    #[default]
+    Json,
+    #[serde(alias = "markdown")]
+    Md,
+    Html,
+}
+
+impl TranscriptFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TranscriptFormat::Json => "application/json",
+            TranscriptFormat::Md => "text/markdown; charset=utf-8",
+            TranscriptFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscriptFormat::Json => "json",
+            TranscriptFormat::Md => "md",
+            TranscriptFormat::Html => "html",
+        }
+    }
+}
+
+fn verdict_label(lang: &Language, record: &Record) -> String {
+    let key = match record.answers.as_ref().and_then(|a| a.verdict.clone()) {
+        Some(Verdict::Yes) => "verdict.yes",
+        Some(Verdict::No) => "verdict.no",
+        Some(Verdict::Unable) => "verdict.unable",
+        Some(Verdict::Final) => "verdict.final",
+        Some(Verdict::Behave) => "verdict.behave",
+        _ => "verdict.na",
+    };
+    t(lang, key)
+}
+
+fn verdict_class(record: &Record) -> &'static str {
+    match record.answers.as_ref().and_then(|a| a.verdict.clone()) {
+        Some(Verdict::Yes) => "yes",
+        Some(Verdict::No) => "no",
+        Some(Verdict::Unable) => "unable",
+        Some(Verdict::Final) => "final",
+        Some(Verdict::Behave) => "behave",
+        _ => "na",
+    }
+}
+
+fn comment(record: &Record) -> Option<&str> {
+    record.answers.as_ref()?.comment.as_deref().filter(|c| !c.trim().is_empty())
+}
+
+/// "Difficulty: Hard · 12 questions · 2025-09-30"
+fn summary(transcript: &Transcript) -> String {
+    let lang = &transcript.lang;
+    let difficulty = t(lang, &format!("difficulty.{}", transcript.difficulty.to_code()));
+    let date = OffsetDateTime::from_unix_timestamp(transcript.exported)
+        .map(|time| time.date())
+        .map(|date| format!("{:04}-{:02}-{:02}", date.year(), date.month() as u8, date.day()))
+        .unwrap_or_default();
+    [
+        tf(lang, "transcript.difficulty", &[&difficulty]),
+        tf(lang, "transcript.questions", &[&transcript.records.len().to_string()]),
+        date,
+    ].join(" · ")
+}
+
+/// Player input must not turn into markup.
+/// Inline text; line breaks are folded, so the text can't leave its list
+/// item or quote.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.split_whitespace().collect::<Vec<_>>().join(" ").chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub fn to_markdown(transcript: &Transcript) -> String {
+    let lang = &transcript.lang;
+    let mut out = format!("# {}\n\n{}\n", t(lang, "transcript.title"), summary(transcript));
+    if let Some(info) = transcript.custom_info.as_ref().and_then(|c| c.comment.as_deref()).filter(|c| !c.is_empty()) {
+        out.push_str(&format!("\n> {}\n", escape_markdown(info)));
+    }
+    out.push('\n');
+    for (i, record) in transcript.records.iter().enumerate() {
+        out.push_str(&format!("{}. **{}** — {}\n", i + 1,
+            escape_markdown(&record.questions.text), verdict_label(lang, record)));
+        if let Some(comment) = comment(record) {
+            out.push_str(&format!("   {}\n", escape_markdown(comment)));
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str = "\
+body { font-family: system-ui, sans-serif; background: #14161b; color: #e6e6e6; max-width: 46em; margin: 2em auto; padding: 0 1em; }
+.summary, .info { opacity: .8; }
+ol { padding-left: 1.5em; }
+li { margin: .8em 0; }
+.badge { display: inline-block; padding: .1em .5em; border-radius: 4px; font-size: .85em; margin-left: .5em; }
+.yes { color: #18c37d; } .no { color: #d8797a; } .unable { color: #d8b779; }
+.final { color: #82aaff; } .behave { color: #ff0000; } .na { color: #999; }
+.comment { opacity: .85; margin-top: .2em; }
+";
+
+/// Standalone page; no scripts, no external resources.
+pub fn to_html(transcript: &Transcript) -> String {
+    let lang = &transcript.lang;
+    let title = escape_html(&t(lang, "transcript.title"));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<h1>{}</h1>\n<p class=\"summary\">{}</p>\n",
+        lang.to_code(), title, HTML_STYLE, title, escape_html(&summary(transcript)));
+    if let Some(info) = transcript.custom_info.as_ref().and_then(|c| c.comment.as_deref()).filter(|c| !c.is_empty()) {
+        out.push_str(&format!("<blockquote class=\"info\">{}</blockquote>\n", escape_html(info)));
+    }
+    out.push_str("<ol>\n");
+    for record in &transcript.records {
+        out.push_str(&format!("<li><span class=\"question\">{}</span><span class=\"badge {}\">{}</span>",
+            escape_html(&record.questions.text), verdict_class(record), escape_html(&verdict_label(lang, record))));
+        if let Some(comment) = comment(record) {
+            out.push_str(&format!("<div class=\"comment\">{}</div>", escape_html(comment)));
+        }
+        out.push_str("</li>\n");
+    }
+    out.push_str("</ol>\n</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_escapes_formatting() {
+        assert_eq!(escape_markdown("**bold** _it_ `code`"), r"\*\*bold\*\* \_it\_ \`code\`");
+        assert_eq!(escape_markdown("[link](http://x) <b> # | \\"), r"\[link\](http://x) \<b\> \# \| \\");
+        assert_eq!(escape_markdown("Are you Einstein?"), "Are you Einstein?");
+    }
+
+    #[test]
+    fn markdown_folds_line_breaks() {
+        assert_eq!(escape_markdown("first\n\n# heading\r\n> quote"), r"first \# heading \> quote");
+    }
+
+    #[test]
+    fn html_escapes_markup() {
+        assert_eq!(escape_html(r#"<script>alert("x" & 'y')</script>"#),
+            "&lt;script&gt;alert(&quot;x&quot; &amp; &#39;y&#39;)&lt;/script&gt;");
+        assert_eq!(escape_html("&lt;"), "&amp;lt;");
+    }
+}
@@ -0,0 +1,122 @@
+use std::ops::Deref;
+
+use axum::extract::{FromRequestParts, Path};
+use axum::http::request::Parts;
+use shared::token::{Token, TokenType};
+use tracing::warn;
+
+use crate::app_error::AppError;
+
+/// Token of the kind the request needs. Malformed, forged and expired tokens
+/// are `InvalidToken`, tokens of another kind `WrongTokenType`.
+pub fn parse_token(token_str: &str, kind: TokenType) -> Result<Token, AppError> {
+    let token = Token::from_string(token_str).map_err(|e| {
+        warn!("invalid token: {} - {}", token_str, e);
+        AppError::InvalidToken
+    })?;
+    match token.get_token_type() {
+        actual if actual == kind => Ok(token),
+        TokenType::Spectator if kind == TokenType::Game => {
+            warn!("spectator token {} used as a game token", token);
+            Err(AppError::SpectatorOnly)
+        }
+        actual => {
+            warn!("{} token {} used as a {} token", actual.name(), token, kind.name());
+            Err(AppError::WrongTokenType(kind.name()))
+        }
+    }
+}
+
+/// Path extractor of a token of one kind; the route must have the single
+/// `{token}` parameter.
+macro_rules! typed_token {
+    ($(#[$meta:meta])* $name:ident => $kind:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name(pub Token);
+
+        impl Deref for $name {
+            type Target = Token;
+            fn deref(&self) -> &Token {
+                &self.0
+            }
+        }
+
+        impl<S: Send + Sync> FromRequestParts<S> for $name {
+            type Rejection = AppError;
+
+            async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, AppError> {
+                let Path(token_str) = Path::<String>::from_request_parts(parts, state).await
+                    .map_err(|_| AppError::InvalidToken)?;
+                parse_token(&token_str, $kind).map(Self)
+            }
+        }
+    };
+}
+
+typed_token!(
+    /// Token of a game, letting its holder play.
+    GameToken => TokenType::Game
+);
+typed_token!(
+    /// Token of a custom game template.
+    TemplateToken => TokenType::GameTemplate
+);
+typed_token!(
+    /// Read-only token of a game.
+    SpectatorToken => TokenType::Spectator
+);
+typed_token!(
+    /// Token of an anonymous player session.
+    SessionToken => TokenType::Session
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_gen::{init_test_tokens, TokenGen};
+
+    fn token(kind: TokenType) -> String {
+        init_test_tokens();
+        Token::new(kind).to_string()
+    }
+
+    #[test]
+    fn matching_kind_parses() {
+        let text = token(TokenType::Game);
+        let parsed = parse_token(&text, TokenType::Game).unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert!(parse_token(&token(TokenType::Spectator), TokenType::Spectator).is_ok());
+    }
+
+    #[test]
+    fn spectator_token_cannot_play() {
+        let result = parse_token(&token(TokenType::Spectator), TokenType::Game);
+        assert!(matches!(result, Err(AppError::SpectatorOnly)));
+    }
+
+    #[test]
+    fn other_kinds_are_wrong_type() {
+        let cases = [
+            (TokenType::Game, TokenType::Spectator),
+            (TokenType::Session, TokenType::Game),
+            (TokenType::GameTemplate, TokenType::Game),
+            (TokenType::Spectator, TokenType::Session),
+        ];
+        for (actual, kind) in cases {
+            let result = parse_token(&token(actual), kind);
+            assert!(matches!(result, Err(AppError::WrongTokenType(name)) if name == kind.name()),
+                "{:?} as {:?}", actual, kind);
+        }
+    }
+
+    #[test]
+    fn malformed_tokens_are_invalid() {
+        let mut forged = token(TokenType::Game).into_bytes();
+        let last = forged.len() - 1;
+        forged[last] = if forged[last] == b'a' { b'b' } else { b'a' };
+        for text in ["", "not a token", std::str::from_utf8(&forged).unwrap()] {
+            assert!(matches!(parse_token(text, TokenType::Game), Err(AppError::InvalidToken)), "{:?}", text);
+        }
+    }
+}
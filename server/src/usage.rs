@@ -0,0 +1,416 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use linked_hash_map::LinkedHashMap;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::{debug, info, warn};
+
+use shared::token::Token;
+
+use crate::config::{Accounting, Config, Price};
+use crate::gpt::{Model, Usage};
+use crate::string_enum;
+
+string_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Purpose {
+        Answer => "answer",
+        Consistency => "consistency",
+        FactSheet => "fact_sheet",
+    }
+}
+
+/// Identifies what a model call was made for.
+#[derive(Debug, Clone)]
+pub struct UsageTag {
+    pub purpose: Purpose,
+    pub game: Option<Token>,
+    pub template: Option<Token>,
+    /// Prompt variant of the game.
+    pub variant: Option<String>,
+}
+
+impl UsageTag {
+    pub fn new(purpose: Purpose) -> Self {
+        Self {
+            purpose,
+            game: None,
+            template: None,
+            variant: None,
+        }
+    }
+
+    pub fn for_game(purpose: Purpose, game: &Token, template: Option<Token>) -> Self {
+        Self {
+            purpose,
+            game: Some(*game),
+            template,
+            variant: None,
+        }
+    }
+
+    pub fn with_variant(mut self, variant: Option<String>) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    pub fn with_purpose(&self, purpose: Purpose) -> Self {
+        Self {
+            purpose,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UsageTotals {
+    pub calls: u64,
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+    pub reasoning_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: &UsageTotals) {
+        self.calls += other.calls;
+        self.input_tokens += other.input_tokens;
+        self.cached_input_tokens += other.cached_input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CallRecord {
+    pub timestamp: i64,
+    pub model: String,
+    pub purpose: String,
+    pub game: Option<String>,
+    pub template: Option<String>,
+    pub usage: UsageTotals,
+}
+
+/// Spend within one calendar period ("2025-09-30" or "2025-09").
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PeriodSpend {
+    pub period: String,
+    pub usage: UsageTotals,
+}
+
+impl PeriodSpend {
+    fn roll(&mut self, period: String) {
+        if self.period != period {
+            self.period = period;
+            self.usage = UsageTotals::default();
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct Ledger {
+    total: UsageTotals,
+    today: PeriodSpend,
+    month: PeriodSpend,
+    per_model: HashMap<String, UsageTotals>,
+    per_purpose: HashMap<String, UsageTotals>,
+    per_template: HashMap<String, UsageTotals>,
+    /// In the order of the last charge, capped at `max_games`.
+    per_game: LinkedHashMap<String, UsageTotals>,
+    #[serde(skip)]
+    recent: VecDeque<CallRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UsageReport {
+    pub total: UsageTotals,
+    pub today: PeriodSpend,
+    pub month: PeriodSpend,
+    pub daily_limit_usd: Option<f64>,
+    pub monthly_limit_usd: Option<f64>,
+    pub per_model: HashMap<String, UsageTotals>,
+    pub per_purpose: HashMap<String, UsageTotals>,
+    pub per_template: HashMap<String, UsageTotals>,
+    pub per_game: HashMap<String, UsageTotals>,
+    pub recent_calls: Vec<CallRecord>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+fn periods() -> (String, String) {
+    let date = OffsetDateTime::now_utc().date();
+    let month = date.month() as u8;
+    (
+        format!("{:04}-{:02}-{:02}", date.year(), month, date.day()),
+        format!("{:04}-{:02}", date.year(), month),
+    )
+}
+
+pub struct UsageTracker {
+    config: Accounting,
+    pricing: HashMap<String, Price>,
+    persist_file: Option<PathBuf>,
+    ledger: Mutex<Ledger>,
+}
+
+impl UsageTracker {
+    pub fn new(config: &Config) -> Self {
+        let tracker = Self {
+            config: config.accounting.clone(),
+            pricing: config.pricing.clone(),
+            persist_file: config.get_accounting_file(),
+            ledger: Mutex::new(Ledger::default()),
+        };
+        if let Err(err) = tracker.load() {
+            warn!("usage ledger not loaded: {}", err);
+        }
+        tracker
+    }
+
+    fn cost(&self, model: Model, usage: &Usage) -> f64 {
+        let Some(price) = self.pricing.get(model.as_str()) else {
+            debug!("no price configured for {}", model);
+            return 0.0;
+        };
+        let cached = usage.input_tokens_details.cached_tokens.min(usage.input_tokens);
+        let cached_price = price.cached_input_per_mtok.unwrap_or(price.input_per_mtok);
+        ((usage.input_tokens - cached) as f64 * price.input_per_mtok
+            + cached as f64 * cached_price
+            + usage.output_tokens as f64 * price.output_per_mtok) / 1_000_000.0
+    }
+
+    /// Adds the call to the ledger and returns its cost.
+    pub fn record(&self, model: Model, tag: &UsageTag, usage: &Usage) -> f64 {
+        let call = UsageTotals {
+            calls: 1,
+            input_tokens: usage.input_tokens,
+            cached_input_tokens: usage.input_tokens_details.cached_tokens,
+            output_tokens: usage.output_tokens,
+            reasoning_tokens: usage.output_tokens_details.reasoning_tokens,
+            cost_usd: self.cost(model, usage),
+        };
+        let cost_usd = call.cost_usd;
+        info!("usage: model={} purpose={} game={} in={} out={} reasoning={} cost=${:.6}",
+            model, tag.purpose, tag.game.map(|t| t.to_string()).unwrap_or_default(),
+            call.input_tokens, call.output_tokens, call.reasoning_tokens, call.cost_usd);
+
+        let (day, month) = periods();
+        let mut ledger = self.ledger.lock().unwrap();
+        ledger.today.roll(day);
+        ledger.month.roll(month);
+        ledger.total.add(&call);
+        ledger.today.usage.add(&call);
+        ledger.month.usage.add(&call);
+        ledger.per_model.entry(model.to_string()).or_default().add(&call);
+        ledger.per_purpose.entry(tag.purpose.to_string()).or_default().add(&call);
+        if let Some(template) = &tag.template {
+            ledger.per_template.entry(template.to_string()).or_default().add(&call);
+        }
+        if let Some(game) = &tag.game {
+            let game = game.to_string();
+            let mut totals = ledger.per_game.remove(&game).unwrap_or_default();
+            totals.add(&call);
+            ledger.per_game.insert(game, totals);
+            while ledger.per_game.len() > self.config.max_games {
+                ledger.per_game.pop_front();
+            }
+        }
+
+        ledger.recent.push_back(CallRecord {
+            timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            model: model.to_string(),
+            purpose: tag.purpose.to_string(),
+            game: tag.game.map(|t| t.to_string()),
+            template: tag.template.map(|t| t.to_string()),
+            usage: call,
+        });
+        while ledger.recent.len() > self.config.recent_calls {
+            ledger.recent.pop_front();
+        }
+        cost_usd
+    }
+
+    /// Fails with the exhausted period once the daily or monthly spend reaches its limit.
+    pub fn check_budget(&self) -> Result<(), BudgetPeriod> {
+        let (day, month) = periods();
+        let mut ledger = self.ledger.lock().unwrap();
+        ledger.today.roll(day);
+        ledger.month.roll(month);
+        if self.config.daily_limit_usd.is_some_and(|limit| ledger.today.usage.cost_usd >= limit) {
+            return Err(BudgetPeriod::Daily);
+        }
+        if self.config.monthly_limit_usd.is_some_and(|limit| ledger.month.usage.cost_usd >= limit) {
+            return Err(BudgetPeriod::Monthly);
+        }
+        Ok(())
+    }
+
+    pub fn report(&self) -> UsageReport {
+        let (day, month) = periods();
+        let mut ledger = self.ledger.lock().unwrap();
+        ledger.today.roll(day);
+        ledger.month.roll(month);
+        UsageReport {
+            total: ledger.total.clone(),
+            today: ledger.today.clone(),
+            month: ledger.month.clone(),
+            daily_limit_usd: self.config.daily_limit_usd,
+            monthly_limit_usd: self.config.monthly_limit_usd,
+            per_model: ledger.per_model.clone(),
+            per_purpose: ledger.per_purpose.clone(),
+            per_template: ledger.per_template.clone(),
+            per_game: ledger.per_game.iter().map(|(game, usage)| (game.clone(), usage.clone())).collect(),
+            recent_calls: ledger.recent.iter().cloned().collect(),
+        }
+    }
+
+    fn load(&self) -> Result<()> {
+        let Some(path) = &self.persist_file else {
+            return Ok(());
+        };
+        if !path.exists() {
+            return Ok(());
+        }
+        let content = std::fs::read_to_string(path)?;
+        *self.ledger.lock().unwrap() = serde_json::from_str(&content)?;
+        info!("usage ledger loaded from {:?}", path);
+        Ok(())
+    }
+
+    /// Writes the ledger to the persist file, if configured.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.persist_file else {
+            return Ok(());
+        };
+        let content = serde_json::to_string(&*self.ledger.lock().unwrap())?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub fn persist_interval(&self) -> Option<std::time::Duration> {
+        self.persist_file.as_ref()?;
+        Some(std::time::Duration::from_secs(self.config.persist_interval_secs.max(1)))
+    }
+}
+
+// Global usage tracker, so every model call is accounted wherever it's made.
+static USAGE_TRACKER: std::sync::OnceLock<UsageTracker> = std::sync::OnceLock::new();
+
+pub fn init_usage(config: &Config) {
+    USAGE_TRACKER.get_or_init(|| UsageTracker::new(config));
+}
+
+pub fn get_usage_tracker() -> &'static UsageTracker {
+    USAGE_TRACKER.get().expect("Usage tracker not initialized. Call init_usage() first.")
+}
+
+pub fn record(model: Model, tag: &UsageTag, usage: &Usage) -> f64 {
+    USAGE_TRACKER.get()
+        .map(|tracker| tracker.record(model, tag, usage))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_gen::{init_test_tokens, TokenGen};
+    use shared::token::TokenType;
+
+    fn tracker(daily_limit_usd: Option<f64>, monthly_limit_usd: Option<f64>, max_games: usize) -> UsageTracker {
+        UsageTracker {
+            config: Accounting { daily_limit_usd, monthly_limit_usd, max_games, ..Accounting::default() },
+            pricing: HashMap::from([
+                ("gpt-5".to_string(), Price { input_per_mtok: 1.25, cached_input_per_mtok: Some(0.125), output_per_mtok: 10.0 }),
+                ("gpt-5-mini".to_string(), Price { input_per_mtok: 0.25, cached_input_per_mtok: None, output_per_mtok: 2.0 }),
+            ]),
+            persist_file: None,
+            ledger: Mutex::new(Ledger::default()),
+        }
+    }
+
+    fn usage(input: u64, cached: u64, output: u64) -> Usage {
+        serde_json::from_value(serde_json::json!({
+            "input_tokens": input,
+            "output_tokens": output,
+            "input_tokens_details": { "cached_tokens": cached },
+            "output_tokens_details": { "reasoning_tokens": 0 },
+        })).unwrap()
+    }
+
+    fn assert_usd(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-12, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn cost_follows_the_pricing() {
+        let tracker = tracker(None, None, 10);
+        // 600k uncached input, 400k cached input, 100k output
+        assert_usd(tracker.cost(Model::Gpt5, &usage(1_000_000, 400_000, 100_000)), 0.75 + 0.05 + 1.0);
+        // no cached price: cached input costs as much as the rest
+        assert_usd(tracker.cost(Model::Gpt5Mini, &usage(1_000_000, 400_000, 500_000)), 0.25 + 1.0);
+    }
+
+    #[test]
+    fn unpriced_model_is_free() {
+        assert_usd(tracker(None, None, 10).cost(Model::Gpt5Nano, &usage(1_000_000, 0, 1_000_000)), 0.0);
+    }
+
+    #[test]
+    fn record_adds_up_the_totals() {
+        let tracker = tracker(None, None, 10);
+        let tag = UsageTag::new(Purpose::Answer);
+        tracker.record(Model::Gpt5, &tag, &usage(1_000_000, 0, 0));
+        tracker.record(Model::Gpt5Mini, &tag.with_purpose(Purpose::FactSheet), &usage(0, 0, 1_000_000));
+        let report = tracker.report();
+        assert_eq!(report.total.calls, 2);
+        assert_usd(report.total.cost_usd, 1.25 + 2.0);
+        assert_usd(report.today.usage.cost_usd, 3.25);
+        assert_usd(report.per_model["gpt-5-mini"].cost_usd, 2.0);
+        assert_eq!(report.per_purpose["fact_sheet"].output_tokens, 1_000_000);
+        assert_eq!(report.recent_calls.len(), 2);
+    }
+
+    #[test]
+    fn budget_is_enforced_per_period() {
+        let tag = UsageTag::new(Purpose::Answer);
+
+        let daily = tracker(Some(1.0), Some(100.0), 10);
+        daily.record(Model::Gpt5, &tag, &usage(400_000, 0, 0));
+        assert_eq!(daily.check_budget(), Ok(()));
+        daily.record(Model::Gpt5, &tag, &usage(400_000, 0, 0));
+        assert_eq!(daily.check_budget(), Err(BudgetPeriod::Daily));
+
+        let monthly = tracker(None, Some(1.0), 10);
+        monthly.record(Model::Gpt5, &tag, &usage(800_000, 0, 0));
+        assert_eq!(monthly.check_budget(), Err(BudgetPeriod::Monthly));
+    }
+
+    #[test]
+    fn per_game_spend_is_capped() {
+        init_test_tokens();
+        let tracker = tracker(None, None, 2);
+        let games = (0..3).map(|_| Token::new(TokenType::Game)).collect::<Vec<_>>();
+        for game in &games {
+            tracker.record(Model::Gpt5, &UsageTag::for_game(Purpose::Answer, game, None), &usage(1000, 0, 0));
+        }
+        // the third game dropped the first; charging the first again drops the second
+        tracker.record(Model::Gpt5, &UsageTag::for_game(Purpose::Answer, &games[0], None), &usage(1000, 0, 0));
+        tracker.record(Model::Gpt5, &UsageTag::for_game(Purpose::Answer, &games[2], None), &usage(1000, 0, 0));
+        let report = tracker.report();
+        assert_eq!(report.per_game.len(), 2);
+        assert_eq!(report.per_game[&games[0].to_string()].calls, 1);
+        assert_eq!(report.per_game[&games[2].to_string()].calls, 2);
+        assert_eq!(report.total.calls, 5);
+    }
+}
@@ -2,11 +2,11 @@ use anyhow::Error;
 
 pub fn check_question(question: &str) -> Result<(), Error> {
     if question.len() > 120 {
-        return Err(anyhow::anyhow!("Too long question").into());
+        return Err(anyhow::anyhow!("Too long question"));
     }
     let count = question.chars().filter(|c| !c.is_whitespace()).count();
     if count < 5 {
-        return Err(anyhow::anyhow!("Is it even a question?").into());
+        return Err(anyhow::anyhow!("Is it even a question?"));
     }
     Ok(())
 }
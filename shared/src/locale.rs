@@ -1,15 +1,18 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum Language {
     #[serde(rename = "en")]
+    #[default]
     English,
     #[serde(rename = "cs")]
     Czech,
 }
 
 impl Language {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "en" | "english" => Some(Language::English),
@@ -40,18 +43,18 @@ impl Language {
     }
 }
 
-impl Default for Language {
-    fn default() -> Self {
-        Language::English
-    }
-}
-
 #[derive(Clone, Debug)]
 pub struct Translations {
     translations: HashMap<Language, HashMap<String, String>>,
 }
 
 
+impl Default for Translations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Translations {
     pub fn new() -> Self {
         Self {
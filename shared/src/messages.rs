@@ -9,6 +9,7 @@ use crate::locale::{Language};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum Verdict {
     Yes,
     No,
@@ -20,28 +21,41 @@ pub enum Verdict {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Question {
     pub text: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Answer {
     #[serde(default)]
     pub verdict: Option<Verdict>,
     #[serde(default)]
     pub comment: Option<String>,
+    /// The answer was served from the answer cache instead of the model.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
 
     timestamp: i64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Record {
     pub questions: Question,
     #[serde(default)]
     pub answers: Option<Answer>,
+    /// Indexes of the records this answer contradicts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts_with: Vec<usize>,
+    /// The prompt guard found the question suspicious but let it through.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub guard_flagged: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CustomGameInfo {
     pub comment: Option<String>,
 }
@@ -54,15 +68,21 @@ pub enum GameTemplateStatus {
     EmptyIdentity,
     ToLongIdentity,
     NotSet,
+    /// The server has no `[answer.profiles]` entry of the name.
+    UnknownModelProfile,
 }
 
 
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct GameTemplate {
     pub identity: String,
     pub language: Language,
     pub properties: CustomGameInfo,
+    /// Model settings profile of the server the games are answered with.
+    #[serde(default)]
+    pub model_profile: Option<String>,
 }
 
 impl GameTemplate {
@@ -75,12 +95,48 @@ impl GameTemplate {
           return GameTemplateStatus::ToLongIdentity;
         }
 
-        return GameTemplateStatus::Ok;
+        GameTemplateStatus::Ok
+    }
+}
+
+/// Difficulty of a game: how well known the identity is and how much the
+/// model helps.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum Difficulty {
+    /// Common identities; the comments nudge the player.
+    Easy,
+    #[default]
+    Normal,
+    /// Obscure identities, a question budget and no comments at all.
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn from_code(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn to_code(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
     }
 }
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct GameState {
     #[serde(default)]
     pub subject: Option<String>,
@@ -91,11 +147,21 @@ pub struct GameState {
     #[serde(default)]
     pub error: Option<GameError>,
     #[serde(skip_serializing)]
+    #[cfg_attr(feature = "openapi", schema(ignore))]
     pub identity: Option<String>,
+    /// Prompt variant the game is played with; hidden from the player.
+    #[serde(skip_serializing)]
+    #[cfg_attr(feature = "openapi", schema(ignore))]
+    pub variant: Option<String>,
     pub game_ended: bool,
     pub lang: Language,
     pub is_custom: bool,
     pub custom_info: Option<CustomGameInfo>,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Questions the game allows; it's lost when they run out.
+    #[serde(default)]
+    pub question_limit: Option<usize>,
 }
 
 impl Default for GameState {
@@ -106,10 +172,13 @@ impl Default for GameState {
             pending_question: None,
             error: None,
             identity: None,
+            variant: None,
             game_ended: false,
             lang: Language::English,
             is_custom: false,
             custom_info: None,
+            difficulty: Difficulty::Normal,
+            question_limit: None,
         }
     }
 }
@@ -122,11 +191,33 @@ impl GameState {
             }
         }
     }
+
+    /// Clears the comments except the one revealing the identity.
+    pub fn clear_comments_but_final(&mut self) {
+        for record in &mut self.records {
+            if let Some(answer) = record.answers.as_mut().filter(|a| a.verdict != Some(Verdict::Final)) {
+                answer.comment = None;
+            }
+        }
+    }
+
+    /// The question budget ran out before the identity was guessed.
+    pub fn is_out_of_questions(&self) -> bool {
+        let solved = self.records.last()
+            .and_then(|r| r.answers.as_ref())
+            .is_some_and(|a| a.verdict == Some(Verdict::Final));
+        !solved && self.question_limit.is_some_and(|limit| self.records.len() >= limit)
+    }
+
+    pub fn questions_left(&self) -> Option<usize> {
+        self.question_limit.map(|limit| limit.saturating_sub(self.records.len()))
+    }
 }
 
 
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum Status {
     #[serde(rename = "pending")]
     Pending,
@@ -136,7 +227,291 @@ pub enum Status {
     Error,
 }
 
+/// Prefix of the versioned REST API.
+pub const API_V1: &str = "/api/v1";
+
+/// Request of `POST /api/v1/games`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NewGameRequest {
+    /// Language of the game; English if not set.
+    #[serde(default)]
+    pub lang: Option<Language>,
+    /// Normal if not set.
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    /// Player session the game joins the history of.
+    #[serde(default)]
+    pub session: Option<String>,
+}
+
+/// Token of a newly created game or game template.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TokenResponse {
+    pub token: String,
+}
+
+/// Request of `POST /api/v1/games/{token}/questions`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AskRequest {
+    pub question: String,
+}
+
+/// Reply without content, e.g. to an accepted question.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StatusResponse {
+    pub status: Status,
+}
+
+/// How a game of the player's history went.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum GameOutcome {
+    Active,
+    Solved,
+    GaveUp,
+    OutOfQuestions,
+    /// Ended by an operator.
+    Ended,
+}
+
+impl GameOutcome {
+    pub fn to_code(&self) -> &'static str {
+        match self {
+            GameOutcome::Active => "active",
+            GameOutcome::Solved => "solved",
+            GameOutcome::GaveUp => "gave_up",
+            GameOutcome::OutOfQuestions => "out_of_questions",
+            GameOutcome::Ended => "ended",
+        }
+    }
+}
+
+/// A game of `GET /api/v1/sessions/{token}/games`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HistoryGame {
+    pub token: String,
+    /// Unix timestamp of the game creation.
+    pub created: i64,
+    pub lang: Language,
+    pub difficulty: Difficulty,
+    pub questions: usize,
+    pub outcome: GameOutcome,
+    pub is_custom: bool,
+}
+
+/// A live game as listed on the admin dashboard.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AdminGameSummary {
+    pub token: String,
+    pub lang: Language,
+    /// Unix timestamp of the game creation.
+    pub created: i64,
+    /// Version of the server assets (instructions, identities) the game started with.
+    pub asset_version: String,
+    /// Prompt variant of the game.
+    pub variant: Option<String>,
+    pub questions: usize,
+    pub pending: bool,
+    pub game_ended: bool,
+    pub is_custom: bool,
+}
+
+/// Server assets new games start with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AdminAssets {
+    pub version: String,
+    /// Number of identities per language.
+    pub identities: Vec<(Language, usize)>,
+}
+
+/// Outcomes of the games played with a prompt variant since the server start.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AdminVariantStats {
+    pub name: String,
+    /// Current weight; `None` when the variant was removed from the config.
+    pub weight: Option<u32>,
+    pub games: u64,
+    pub answers: u64,
+    pub solves: u64,
+    pub give_ups: u64,
+    /// Solved games per started game.
+    pub solve_rate: f64,
+    /// UNABLE answers per answer.
+    pub unable_rate: f64,
+    /// BEHAVE answers per answer.
+    pub behave_rate: f64,
+    /// Questions of the solved games, on average.
+    pub avg_questions_to_solve: Option<f64>,
+    /// Model spend of the variant's games, consistency checks included.
+    pub cost_usd: f64,
+    pub cost_per_game_usd: f64,
+}
+
+/// Full game including the hidden identity and all comments.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AdminGameDetail {
+    pub summary: AdminGameSummary,
+    pub identity: String,
+    /// Template the game was created from, if any.
+    pub template: Option<String>,
+    pub state: GameState,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AdminTemplate {
+    pub token: String,
+    pub template: GameTemplate,
+}
+
+/// Version of the transcript format, bumped on incompatible changes.
+pub const TRANSCRIPT_VERSION: u32 = 1;
+
+/// Finished game as exported for sharing and archiving; the identity is only
+/// in the comments the player saw.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Transcript {
+    pub version: u32,
+    /// Unix timestamp of the export.
+    pub exported: i64,
+    pub lang: Language,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub custom_info: Option<CustomGameInfo>,
+    #[serde(default)]
+    pub question_limit: Option<usize>,
+    pub records: Vec<Record>,
+}
+
+impl Transcript {
+    /// Transcript of the game as the player sees it.
+    pub fn from_game(state: &GameState) -> Self {
+        Self {
+            version: TRANSCRIPT_VERSION,
+            exported: OffsetDateTime::now_utc().unix_timestamp(),
+            lang: state.lang.clone(),
+            difficulty: state.difficulty,
+            custom_info: state.custom_info.clone(),
+            question_limit: state.question_limit,
+            records: state.records.clone(),
+        }
+    }
+
+    /// Reads an exported JSON transcript; newer versions are refused.
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let transcript = serde_json::from_str::<Self>(json).map_err(|e| e.to_string())?;
+        if transcript.version > TRANSCRIPT_VERSION {
+            return Err(format!("transcript version {} is not supported", transcript.version));
+        }
+        Ok(transcript)
+    }
+
+    /// Ended game with the records, for the board renderers.
+    pub fn to_game_state(&self) -> GameState {
+        GameState {
+            records: self.records.clone(),
+            game_ended: true,
+            lang: self.lang.clone(),
+            is_custom: self.custom_info.is_some(),
+            custom_info: self.custom_info.clone(),
+            difficulty: self.difficulty,
+            question_limit: self.question_limit,
+            ..Default::default()
+        }
+    }
+}
+
+/// Version of the error payload contract, bumped on incompatible changes.
+pub const ERROR_VERSION: u32 = 1;
+
+/// Stable machine readable error codes; clients should match on these
+/// rather than on the (localized) message.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ErrorCode {
+    InvalidToken,
+    /// A valid token of another kind, e.g. a template token for a game.
+    WrongTokenType,
+    GameNotFound,
+    InactiveGame,
+    /// The request needs a finished game.
+    GameInProgress,
+    /// A spectator token used where the game token is needed.
+    SpectatorOnly,
+    Pending,
+    Timeout,
+    InvalidInput,
+    InvalidGameTemplate,
+    Unauthorized,
+    RateLimited,
+    BudgetExceeded,
+    NotFound,
+    ServerBusy,
+    InternalServerError,
+}
+
+impl ErrorCode {
+    pub fn locale_key(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidToken => "error.invalid_token",
+            ErrorCode::WrongTokenType => "error.wrong_token_type",
+            ErrorCode::GameNotFound => "error.game_not_found",
+            ErrorCode::InactiveGame => "error.inactive_game",
+            ErrorCode::GameInProgress => "error.game_in_progress",
+            ErrorCode::SpectatorOnly => "error.spectator_only",
+            ErrorCode::Pending => "error.pending",
+            ErrorCode::Timeout => "error.timeout",
+            ErrorCode::InvalidInput => "error.invalid_input",
+            ErrorCode::InvalidGameTemplate => "error.invalid_game_template",
+            ErrorCode::Unauthorized => "error.unauthorized",
+            ErrorCode::RateLimited => "error.rate_limited",
+            ErrorCode::BudgetExceeded => "error.budget_exceeded",
+            ErrorCode::NotFound => "error.not_found",
+            ErrorCode::ServerBusy => "error.server_busy",
+            ErrorCode::InternalServerError => "error.internal_server_error",
+        }
+    }
+}
+
+/// Body of every failed API request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorResponse {
+    pub status: Status,
+    pub version: u32,
+    pub code: ErrorCode,
+    /// Localized by the game language or `Accept-Language`.
+    pub message: String,
+    /// The client should drop its token and start a new game.
+    #[serde(default)]
+    pub invalid_token: bool,
+    /// Seconds to wait before retrying, for `RateLimited`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
+
+impl std::fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ErrorResponse {}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum GameError {
     #[serde(rename = "error")]
     GPTError(String),
@@ -144,6 +519,7 @@ pub enum GameError {
 
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ServerResponse<Content: Serialize> {
     pub status: Status,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -192,11 +568,18 @@ pub fn parse_reply(s: &str) -> Option<(&str, &str)> {
         .map(|(tok, cmt)| (tok.trim(), cmt.trim()))
 }
 
+impl Default for Answer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Answer {
     pub fn new() -> Self {
         Self {
             verdict: None,
             comment: None,
+            cached: false,
             timestamp: time::OffsetDateTime::now_utc().unix_timestamp(),
         }
     }
@@ -205,6 +588,7 @@ impl Answer {
         Self {
             verdict: Some(Verdict::Pending),
             comment: None,
+            cached: false,
             timestamp: 0,
         }
     }
@@ -212,12 +596,37 @@ impl Answer {
     pub fn get_final_answer(result: &str) -> Self {
         Self {
             verdict: Some(Verdict::Final),
-            comment: Some(format!("{}", result)),
+            comment: Some(result.to_string()),
+            cached: false,
+            timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+        }
+    }
+
+    pub fn get_behave_answer(comment: &str) -> Self {
+        Self::from_verdict(Verdict::Behave, comment)
+    }
+
+    pub fn from_verdict(verdict: Verdict, comment: &str) -> Self {
+        Self {
+            verdict: Some(verdict),
+            comment: Some(comment.to_string()),
+            cached: false,
             timestamp: OffsetDateTime::now_utc().unix_timestamp(),
         }
     }
 
-    pub fn parse_from_string(input: &str) -> Self {
+    /// Copy of the answer served from the answer cache.
+    pub fn as_cached(&self) -> Self {
+        Self {
+            cached: true,
+            timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            ..self.clone()
+        }
+    }
+
+    /// Legacy parser of the "TOKEN; comment" reply format. `weird_comment` is
+    /// used when the reply doesn't follow the format at all.
+    pub fn parse_from_string(input: &str, weird_comment: &str) -> Self {
         if let Some((token, comment)) = parse_reply(input) {
             let verdict = match token {
                 "YES" => Verdict::Yes,
@@ -230,12 +639,14 @@ impl Answer {
             Self {
                 verdict: Some(verdict),
                 comment: Some(comment.to_string()),
+                cached: false,
                 timestamp: OffsetDateTime::now_utc().unix_timestamp(),
             }
         } else {
             Self {
                 verdict: Some(Verdict::NotSet),
-                comment: Some("Weird question, skip...".to_string()),
+                comment: Some(weird_comment.to_string()),
+                cached: false,
                 timestamp: OffsetDateTime::now_utc().unix_timestamp(),
             }
         }
@@ -247,12 +658,20 @@ impl Record {
         Self {
             questions: Question { text: question },
             answers: None,
+            conflicts_with: vec![],
+            guard_flagged: false,
         }
     }
 
     pub fn set_answer(&mut self, answer: &Answer) {
         self.answers = Some(answer.clone());
     }
+
+    pub fn add_conflict(&mut self, index: usize) {
+        if !self.conflicts_with.contains(&index) {
+            self.conflicts_with.push(index);
+        }
+    }
 }
 
 
@@ -10,6 +10,7 @@ impl Localizer for GameTemplateStatus {
                     GameTemplateStatus::EmptyIdentity => "You muse enter the identity.",
                     GameTemplateStatus::ToLongIdentity => "Identity is too long.",
                     GameTemplateStatus::NotSet => "Not set",
+                    GameTemplateStatus::UnknownModelProfile => "Neznámý profil modelu.",
                 }
             }
 
@@ -19,6 +20,7 @@ impl Localizer for GameTemplateStatus {
                     GameTemplateStatus::EmptyIdentity => "You muse enter the identity.",
                     GameTemplateStatus::ToLongIdentity => "Identity is too long.",
                     GameTemplateStatus::NotSet => "Not set",
+                    GameTemplateStatus::UnknownModelProfile => "Unknown model profile.",
                 }
             }
         }.to_string()
@@ -1,63 +1,277 @@
-#![allow(dead_code)]
+use std::sync::OnceLock;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Result};
 
-pub const TOKEN_LENGTH: usize = 20;
+/// Kind letter, entropy, expiry and signature, all in `TOKEN_CHARSET`.
+const TOKEN_LENGTH: usize = 1 + ENTROPY_CHARS + EXPIRY_CHARS + SIGNATURE_CHARS;
+/// Lowercase base32 without padding.
+const TOKEN_CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Random bytes of a token, 80 bits.
+pub const ENTROPY_BYTES: usize = 10;
+/// Truncated MAC of the rest of the token, 80 bits.
+pub const SIGNATURE_BYTES: usize = 10;
+
+const ENTROPY_CHARS: usize = ENTROPY_BYTES * 8 / 5;
+/// Unix timestamp of the expiry, 35 bits; all zeroes when it never expires.
+const EXPIRY_CHARS: usize = 7;
+const SIGNATURE_CHARS: usize = SIGNATURE_BYTES * 8 / 5;
+const PAYLOAD_LENGTH: usize = TOKEN_LENGTH - SIGNATURE_CHARS;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum TokenType {
     Answer,
     Game,
-    GameTemplate
+    GameTemplate,
+    /// Read-only view of a game.
+    Spectator,
+    /// Anonymous player owning games.
+    Session,
 }
 
 impl TokenType {
-    pub fn leading_byte(&self) -> u8 {
+    fn leading_byte(&self) -> u8 {
         match self {
-            TokenType::Answer => 'a' as u8,
-            TokenType::Game => 'g' as u8,
-            TokenType::GameTemplate => 't' as u8,
+            TokenType::Answer => b'a',
+            TokenType::Game => b'g',
+            TokenType::GameTemplate => b't',
+            TokenType::Spectator => b's',
+            TokenType::Session => b'p',
         }
     }
-    pub fn get_token_type(token: &Token) -> Option<TokenType> {
-        match token.token[0] as char {
-            'a' => Some(TokenType::Answer),
-            'g' => Some(TokenType::Game),
-            't' => Some(TokenType::GameTemplate),
+    fn from_leading_byte(byte: u8) -> Option<TokenType> {
+        match byte {
+            b'a' => Some(TokenType::Answer),
+            b'g' => Some(TokenType::Game),
+            b't' => Some(TokenType::GameTemplate),
+            b's' => Some(TokenType::Spectator),
+            b'p' => Some(TokenType::Session),
             _ => None,
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenType::Answer => "answer",
+            TokenType::Game => "game",
+            TokenType::GameTemplate => "template",
+            TokenType::Spectator => "spectator",
+            TokenType::Session => "session",
+        }
+    }
+}
+
+/// Keyed MAC of the tokens. Only the server has one; without it tokens are
+/// checked just for their shape.
+pub trait TokenSigner: Send + Sync {
+    fn sign(&self, payload: &[u8]) -> [u8; SIGNATURE_BYTES];
+}
+
+static SIGNER: OnceLock<Box<dyn TokenSigner>> = OnceLock::new();
+
+/// Makes `Token::from_string` verify the signatures and the expiry.
+pub fn install_signer(signer: Box<dyn TokenSigner>) {
+    let _ = SIGNER.set(signer);
+}
+
+/// Base32 of whole 5 byte groups.
+fn encode(bytes: &[u8], out: &mut [u8]) {
+    let mut bits = 0u16;
+    let mut count = 0;
+    let mut i = 0;
+    for b in bytes {
+        bits = (bits << 8) | *b as u16;
+        count += 8;
+        while count >= 5 {
+            count -= 5;
+            out[i] = TOKEN_CHARSET[((bits >> count) & 31) as usize];
+            i += 1;
+        }
+    }
 }
 
+fn encode_expiry(expires: Option<i64>, out: &mut [u8]) {
+    let secs = expires.unwrap_or(0).max(0) as u64;
+    for (i, c) in out.iter_mut().rev().enumerate() {
+        *c = TOKEN_CHARSET[((secs >> (5 * i)) & 31) as usize];
+    }
+}
+
+fn decode_expiry(chars: &[u8]) -> Option<i64> {
+    let secs = chars.iter().fold(0i64, |secs, c| {
+        let value = TOKEN_CHARSET.iter().position(|x| x == c).unwrap_or(0) as i64;
+        (secs << 5) | value
+    });
+    (secs != 0).then_some(secs)
+}
+
+fn signature(signer: &dyn TokenSigner, payload: &[u8]) -> [u8; SIGNATURE_CHARS] {
+    let mut out = [0u8; SIGNATURE_CHARS];
+    encode(&signer.sign(payload), &mut out);
+    out
+}
+
+/// Compares in time independent of the first difference; only the length
+/// may leak. For secrets like the signatures and the admin token.
+pub fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Only made by `sign` and `from_string`, so the kind always matches the
+/// leading byte.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
 pub struct Token {
-    pub token: [u8; TOKEN_LENGTH],
+    token: [u8; TOKEN_LENGTH],
+    kind: TokenType,
 }
 
 impl Token {
+    /// Signed token of the kind; `entropy` must come from a CSPRNG.
+    pub fn sign(kind: TokenType, entropy: &[u8; ENTROPY_BYTES], expires: Option<i64>) -> Result<Self> {
+        let Some(signer) = SIGNER.get() else {
+            bail!("No token signer installed");
+        };
+        let mut token = [0u8; TOKEN_LENGTH];
+        token[0] = kind.leading_byte();
+        encode(entropy, &mut token[1..1 + ENTROPY_CHARS]);
+        encode_expiry(expires, &mut token[1 + ENTROPY_CHARS..PAYLOAD_LENGTH]);
+        let signature = signature(signer.as_ref(), &token[..PAYLOAD_LENGTH]);
+        token[PAYLOAD_LENGTH..].copy_from_slice(&signature);
+        Ok(Self { token, kind })
+    }
+
     pub fn from_string(token_str: &str) -> Result<Self> {
-        let bytes = token_str.bytes();
-        if bytes.len() == TOKEN_LENGTH {
-            let mut token = [0u8; TOKEN_LENGTH];
-            for (i, b) in bytes.enumerate() {
-                token[i] = b;
+        let bytes = token_str.as_bytes();
+        if bytes.len() != TOKEN_LENGTH || !bytes.iter().all(|b| TOKEN_CHARSET.contains(b)) {
+            bail!("Not a token");
+        }
+        let Some(kind) = TokenType::from_leading_byte(bytes[0]) else {
+            bail!("Not a token");
+        };
+        let mut token = [0u8; TOKEN_LENGTH];
+        token.copy_from_slice(bytes);
+        let token = Self { token, kind };
+        if let Some(signer) = SIGNER.get() {
+            let expected = signature(signer.as_ref(), &token.token[..PAYLOAD_LENGTH]);
+            if !same(&expected, &token.token[PAYLOAD_LENGTH..]) {
+                bail!("Bad token signature");
+            }
+            if token.expires().is_some_and(|expires| expires <= time::OffsetDateTime::now_utc().unix_timestamp()) {
+                bail!("Token expired");
             }
-            TokenType::get_token_type(&Self { token }).context("Not a token")?;
-            return Ok(Self { token });
         }
-        Err(anyhow::anyhow!("Not a token"))
+        Ok(token)
     }
 
     pub fn get_token_type(&self) -> TokenType {
-        TokenType::get_token_type(self).unwrap()
+        self.kind
     }
 
+    /// Unix timestamp after which the token isn't accepted.
+    fn expires(&self) -> Option<i64> {
+        decode_expiry(&self.token[1 + ENTROPY_CHARS..PAYLOAD_LENGTH])
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keyless stand-in of the server HMAC, good enough to detect changes.
+    struct TestSigner;
+
+    impl TokenSigner for TestSigner {
+        fn sign(&self, payload: &[u8]) -> [u8; SIGNATURE_BYTES] {
+            let mut hash = 0xcbf29ce484222325u64;
+            let mut signature = [0u8; SIGNATURE_BYTES];
+            for byte in signature.iter_mut() {
+                for b in payload {
+                    hash = (hash ^ *b as u64).wrapping_mul(0x100000001b3);
+                }
+                *byte = hash as u8;
+            }
+            signature
+        }
+    }
+
+    fn signed(kind: TokenType, expires: Option<i64>) -> Token {
+        install_signer(Box::new(TestSigner));
+        Token::sign(kind, &[7u8; ENTROPY_BYTES], expires).unwrap()
+    }
+
+    fn now() -> i64 {
+        time::OffsetDateTime::now_utc().unix_timestamp()
+    }
 
-    pub fn to_string(&self) -> String {
-        String::from_utf8_lossy(&self.token).to_string()
+    /// Replaces the character at `index` with another one of the charset.
+    fn tampered(token: &Token, index: usize) -> String {
+        let mut bytes = token.to_string().into_bytes();
+        bytes[index] = if bytes[index] == b'a' { b'b' } else { b'a' };
+        String::from_utf8(bytes).unwrap()
     }
 
-    pub fn to_str(&self) -> &str {
-        std::str::from_utf8(&self.token).unwrap()
+    #[test]
+    fn round_trip() {
+        for kind in [TokenType::Game, TokenType::Spectator, TokenType::Session] {
+            let token = signed(kind, None);
+            let text = token.to_string();
+            assert_eq!(text.len(), TOKEN_LENGTH);
+            assert_eq!(text.as_bytes()[0], kind.leading_byte());
+            let parsed = Token::from_string(&text).unwrap();
+            assert_eq!(parsed, token);
+            assert_eq!(parsed.get_token_type(), kind);
+        }
+    }
+
+    #[test]
+    fn bad_signature_is_rejected() {
+        let token = signed(TokenType::Game, None);
+        assert!(Token::from_string(&tampered(&token, TOKEN_LENGTH - 1)).is_err());
+        assert!(Token::from_string(&tampered(&token, 1)).is_err());
+    }
+
+    #[test]
+    fn changed_kind_is_rejected() {
+        let token = signed(TokenType::Spectator, None);
+        let mut text = token.to_string().into_bytes();
+        text[0] = TokenType::Game.leading_byte();
+        assert!(Token::from_string(std::str::from_utf8(&text).unwrap()).is_err());
+    }
+
+    #[test]
+    fn expiry() {
+        let expires = now() + 3600;
+        let token = signed(TokenType::Game, Some(expires));
+        assert_eq!(token.expires(), Some(expires));
+        assert!(Token::from_string(&token.to_string()).is_ok());
+
+        let expired = signed(TokenType::Game, Some(now() - 1));
+        assert!(Token::from_string(&expired.to_string()).is_err());
+
+        assert_eq!(signed(TokenType::Game, None).expires(), None);
+    }
+
+    #[test]
+    fn not_a_token() {
+        let token = signed(TokenType::Game, None).to_string();
+        assert!(Token::from_string("").is_err());
+        assert!(Token::from_string(&token[1..]).is_err());
+        assert!(Token::from_string(&token.to_uppercase()).is_err());
+        assert!(Token::from_string(&format!("x{}", &token[1..])).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn same_compares_whole_slices() {
+        assert!(same(b"secret", b"secret"));
+        assert!(same(b"", b""));
+        assert!(!same(b"secret", b"secreT"));
+        assert!(!same(b"secret", b"secret2"));
+        assert!(!same(b"secret", b""));
+    }
+}